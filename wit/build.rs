@@ -0,0 +1,55 @@
+use std::{env, fs, io, path::Path};
+
+use sha2::{Digest, Sha256};
+
+fn collect(root: &Path, dir: &Path, files: &mut Vec<(String, String)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect(root, &path, files)?;
+        } else if let Ok(name) = path.strip_prefix(root) {
+            let name = name.to_string_lossy().replace('\\', "/");
+            files.push((name, path.to_string_lossy().into_owned()));
+        }
+    }
+    Ok(())
+}
+
+fn hashed_name(name: &str, content: &[u8]) -> String {
+    let digest = Sha256::digest(content);
+    let hash: String = digest[..8].iter().map(|b| format!("{b:02x}")).collect();
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.ends_with('/') => format!("{stem}.{hash}.{ext}"),
+        _ => format!("{name}.{hash}"),
+    }
+}
+
+fn main() -> io::Result<()> {
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let assets_dir = Path::new(&manifest_dir).join("../assets");
+    println!("cargo:rerun-if-changed={}", assets_dir.display());
+
+    let mut files = vec![];
+    if assets_dir.is_dir() {
+        collect(&assets_dir, &assets_dir, &mut files)?;
+    } else {
+        println!(
+            "cargo:warning=no assets found in {}, run `pnpm build` first",
+            assets_dir.display()
+        );
+    }
+    files.sort();
+
+    let mut code = String::from("static ASSETS: &[(&str, &str, &[u8])] = &[\n");
+    for (name, path) in files {
+        let hashed = hashed_name(&name, &fs::read(&path)?);
+        code.push_str(&format!(
+            "    ({name:?}, {hashed:?}, include_bytes!({path:?})),\n"
+        ));
+    }
+    code.push_str("];\n");
+    fs::write(
+        Path::new(&env::var("OUT_DIR").unwrap_or_default()).join("assets.rs"),
+        code,
+    )
+}
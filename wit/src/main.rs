@@ -19,7 +19,17 @@ async fn main() -> tokio::io::Result<()> {
         LevelFilter::INFO
     };
 
+    // Requires building with `--features console` and
+    // `RUSTFLAGS="--cfg tokio_unstable"`, then setting `WIT_TOKIO_CONSOLE=1`.
+    #[cfg(feature = "console")]
+    let console_layer = std::env::var("WIT_TOKIO_CONSOLE")
+        .is_ok_and(|v| v == "1" || v == "true")
+        .then(console_subscriber::spawn);
+    #[cfg(not(feature = "console"))]
+    let console_layer: Option<tracing_subscriber::layer::Identity> = None;
+
     tracing_subscriber::registry()
+        .with(console_layer)
         .with(tracing_subscriber::fmt::layer())
         .with(
             EnvFilter::builder()
@@ -28,6 +38,8 @@ async fn main() -> tokio::io::Result<()> {
         )
         .init();
 
+    router::install_panic_hook();
+
     let bind_address = std::env::var("WIT_BIND_ADDRESS")
         .map(|s| {
             s.parse::<IpAddr>().unwrap_or_else(|_| {
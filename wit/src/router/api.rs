@@ -1,139 +1,1361 @@
 use axum::{
-    extract::{rejection::PathRejection, Path, State},
-    http::StatusCode,
-    response::{IntoResponse, Response},
-    routing::get,
+    extract::{
+        rejection::{FormRejection, JsonRejection, PathRejection, QueryRejection},
+        Form, Path, Query, Request, State,
+    },
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{AppendHeaders, IntoResponse, Response},
+    routing::{get, post, put},
     Json, Router,
 };
-use serde::Serialize;
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::service::git::{
-    model::{
-        GitBlob, GitBranch, GitCommit, GitIndex, GitOid, GitReference, GitRemote, GitStatus,
-        GitTag, GitTree,
+use crate::service::{
+    git::{
+        model::{
+            GitBlameHunk, GitBlob, GitBranch, GitBranchDivergence, GitCherry, GitCodeOwner,
+            GitCommit, GitCommitDetail, GitCommitTags, GitDefaultBranch, GitDiffFile, GitIndex,
+            GitLicense, GitManifest, GitMergeResult, GitObject, GitObjectType, GitOid, GitPack,
+            GitPackVerification, GitReference, GitRemote, GitRemoteRef, GitStatus, GitTag, GitTree,
+            GitTreeStats, GitUnreleased,
+        },
+        BlobSpec, DiffOptions, GitError, GitRepository, GitResult, IgnoreWhitespace,
+        ListBranchOptions, ListCommitOptions, ListReferenceOptions, MergeStrategy,
+        SearchCommitOptions, SortOptions, MAX_MANIFEST_ENTRIES,
+    },
+    search::{
+        self,
+        model::{CodeMatch, PathMatch},
+        SearchError,
     },
-    GitError, GitRepository,
 };
 
-use super::AppState;
+use super::{
+    auth::require_write_token, fields::select_fields, hooks, limits::limit_expensive,
+    pagination::PageQuery, request_id::current_request_id, AppState,
+};
 
 pub(crate) type ApiResult<T> = Result<T, ApiError>;
 
 #[derive(Debug)]
 pub(crate) enum ApiError {
+    FormRejection(FormRejection),
     Git(GitError),
+    JsonRejection(JsonRejection),
     PathRejection(PathRejection),
+    QueryRejection(QueryRejection),
+    Overloaded(u64),
+    Panic,
+    Profile(pprof::Error),
+    Search(SearchError),
+    Timeout,
+    Unauthorized,
 }
 
 impl std::fmt::Display for ApiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            ApiError::FormRejection(e) => write!(f, "FormRejection: {e}"),
             ApiError::Git(e) => write!(f, "GitError: {e}"),
+            ApiError::JsonRejection(e) => write!(f, "JsonRejection: {e}"),
             ApiError::PathRejection(e) => write!(f, "PathRejection: {e}"),
+            ApiError::QueryRejection(e) => write!(f, "QueryRejection: {e}"),
+            ApiError::Search(e) => write!(f, "SearchError: {e}"),
+            ApiError::Overloaded(_) => write!(f, "Overloaded"),
+            ApiError::Panic => write!(f, "Panic"),
+            ApiError::Profile(e) => write!(f, "ProfileError: {e}"),
+            ApiError::Timeout => write!(f, "Timeout"),
+            ApiError::Unauthorized => write!(f, "Unauthorized"),
         }
     }
 }
 
 impl std::error::Error for ApiError {}
 
+impl From<FormRejection> for ApiError {
+    fn from(e: FormRejection) -> Self {
+        ApiError::FormRejection(e)
+    }
+}
+
 impl From<GitError> for ApiError {
     fn from(e: GitError) -> Self {
         ApiError::Git(e)
     }
 }
 
+impl From<JsonRejection> for ApiError {
+    fn from(e: JsonRejection) -> Self {
+        ApiError::JsonRejection(e)
+    }
+}
+
 impl From<PathRejection> for ApiError {
     fn from(e: PathRejection) -> Self {
         ApiError::PathRejection(e)
     }
 }
 
+impl From<pprof::Error> for ApiError {
+    fn from(e: pprof::Error) -> Self {
+        ApiError::Profile(e)
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(e: QueryRejection) -> Self {
+        ApiError::QueryRejection(e)
+    }
+}
+
+impl From<SearchError> for ApiError {
+    fn from(e: SearchError) -> Self {
+        match e {
+            SearchError::Git(e) => ApiError::Git(e),
+            e => ApiError::Search(e),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ErrorCode {
+    Conflict,
+    Internal,
+    InvalidArgument,
+    InvalidBody,
+    InvalidForm,
+    InvalidPatch,
+    InvalidPath,
+    InvalidPattern,
+    InvalidQuery,
+    InvalidRef,
+    ObjectNotFound,
+    Overloaded,
+    RefNotFound,
+    RepositoryNotFound,
+    Timeout,
+    TooLarge,
+    Unauthorized,
+}
+
+/// The same name as in JSON bodies, e.g. `object_not_found`.
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.serialize(f)
+    }
+}
+
+impl ApiError {
+    pub(crate) fn code(&self) -> ErrorCode {
+        match self {
+            ApiError::FormRejection(_) => ErrorCode::InvalidForm,
+            ApiError::Git(e) => match e {
+                GitError::Cancelled => ErrorCode::Internal,
+                GitError::Conflict(_) => ErrorCode::Conflict,
+                GitError::InvalidArgument(_) => ErrorCode::InvalidArgument,
+                GitError::InvalidPatch(_) => ErrorCode::InvalidPatch,
+                GitError::InvalidReference(_) => ErrorCode::InvalidRef,
+                GitError::ObjectNotFound(_) => ErrorCode::ObjectNotFound,
+                GitError::ReferenceNotFound(_) => ErrorCode::RefNotFound,
+                GitError::RepositoryNotFound(_) => ErrorCode::RepositoryNotFound,
+                GitError::Timeout => ErrorCode::Timeout,
+                GitError::TooLarge(_) => ErrorCode::TooLarge,
+                GitError::Unhandled(_) => ErrorCode::Internal,
+            },
+            ApiError::JsonRejection(_) => ErrorCode::InvalidBody,
+            ApiError::PathRejection(_) => ErrorCode::InvalidPath,
+            ApiError::QueryRejection(_) => ErrorCode::InvalidQuery,
+            ApiError::Search(e) => match e {
+                SearchError::InvalidPattern(_) => ErrorCode::InvalidPattern,
+                _ => ErrorCode::Internal,
+            },
+            ApiError::Overloaded(_) => ErrorCode::Overloaded,
+            ApiError::Panic => ErrorCode::Internal,
+            ApiError::Profile(pprof::Error::Running) => ErrorCode::Conflict,
+            ApiError::Profile(_) => ErrorCode::Internal,
+            ApiError::Timeout => ErrorCode::Timeout,
+            ApiError::Unauthorized => ErrorCode::Unauthorized,
+        }
+    }
+}
+
 impl From<ApiError> for (StatusCode, String) {
     fn from(e: ApiError) -> Self {
         match e {
+            ApiError::FormRejection(e) => (StatusCode::BAD_REQUEST, format!("FormRejection: {e}")),
             ApiError::Git(e) => match e {
+                GitError::Cancelled => (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    String::from("Git operation cancelled"),
+                ),
+                GitError::Conflict(message) => (StatusCode::CONFLICT, message),
+                GitError::InvalidArgument(message) => (
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid argument: {message}"),
+                ),
+                GitError::InvalidPatch(message) => {
+                    (StatusCode::BAD_REQUEST, format!("Invalid patch: {message}"))
+                }
+                GitError::InvalidReference(message) => (
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid git reference: {message}"),
+                ),
                 GitError::ObjectNotFound(message) => (
                     StatusCode::NOT_FOUND,
                     format!("Git object not found: {message}"),
                 ),
+                GitError::ReferenceNotFound(message) => (
+                    StatusCode::NOT_FOUND,
+                    format!("Git reference not found: {message}"),
+                ),
                 GitError::RepositoryNotFound(p) => (
                     StatusCode::NOT_FOUND,
                     format!("Git repository {p:?} not found"),
                 ),
+                GitError::Timeout => (
+                    StatusCode::GATEWAY_TIMEOUT,
+                    String::from("Git operation timed out"),
+                ),
+                GitError::TooLarge(message) => (StatusCode::PAYLOAD_TOO_LARGE, message),
                 GitError::Unhandled(_) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")),
             },
+            ApiError::JsonRejection(e) => (StatusCode::BAD_REQUEST, format!("JsonRejection: {e}")),
             ApiError::PathRejection(e) => (StatusCode::BAD_REQUEST, format!("PathRejection: {e}")),
+            ApiError::QueryRejection(e) => {
+                (StatusCode::BAD_REQUEST, format!("QueryRejection: {e}"))
+            }
+            ApiError::Search(e) => match e {
+                SearchError::InvalidPattern(message) => (
+                    StatusCode::BAD_REQUEST,
+                    format!("Invalid search pattern: {message}"),
+                ),
+                _ => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")),
+            },
+            ApiError::Overloaded(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                String::from("Server is overloaded, retry later"),
+            ),
+            ApiError::Panic => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                String::from("Internal server error"),
+            ),
+            ApiError::Profile(pprof::Error::Running) => (
+                StatusCode::CONFLICT,
+                String::from("A profile is already being taken"),
+            ),
+            ApiError::Profile(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{e}")),
+            ApiError::Timeout => (
+                StatusCode::REQUEST_TIMEOUT,
+                String::from("Request timed out"),
+            ),
+            ApiError::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                String::from("Missing or invalid credentials"),
+            ),
         }
     }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        let code = self.code();
+        let retry_after = match self {
+            ApiError::Overloaded(seconds) => Some(seconds),
+            _ => None,
+        };
         let (status, message) = self.into();
-        (status, Json(ErrorResponse { message })).into_response()
+        let error = ErrorResponse {
+            code,
+            message,
+            request_id: current_request_id(),
+        };
+        let mut response = (status, Json(error.clone())).into_response();
+        response.extensions_mut().insert(error);
+        if let Some(seconds) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
     }
 }
 
+/// Each item of a batch response succeeds or fails on its own.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BatchItem<T> {
+    Error(BatchItemError),
+    Ok(T),
+}
+
 #[derive(Debug, Serialize)]
+struct BatchItemError {
+    code: ErrorCode,
+    message: String,
+}
+
+impl<T> From<GitResult<T>> for BatchItem<T> {
+    fn from(result: GitResult<T>) -> Self {
+        match result {
+            Ok(item) => BatchItem::Ok(item),
+            Err(e) => {
+                let e = ApiError::from(e);
+                let code = e.code();
+                let (_, message) = e.into();
+                BatchItem::Error(BatchItemError { code, message })
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
 struct ErrorResponse {
+    code: ErrorCode,
     message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ProblemResponse {
+    code: ErrorCode,
+    detail: String,
+    instance: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+    status: u16,
+    title: &'static str,
+}
+
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Whether `Accept` lists `media_type`, ignoring wildcards and parameters.
+pub(super) fn accepts(headers: &HeaderMap, media_type: &str) -> bool {
+    headers
+        .get_all(header::ACCEPT)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|v| v.split(';').next().is_some_and(|t| t.trim() == media_type))
+}
+
+pub(super) async fn negotiate_problem(request: Request, next: Next) -> Response {
+    let wants_problem = accepts(request.headers(), PROBLEM_JSON);
+    let instance = request.uri().path().to_string();
+    let response = next.run(request).await;
+    if !wants_problem {
+        return response;
+    }
+    let Some(error) = response.extensions().get::<ErrorResponse>().cloned() else {
+        return response;
+    };
+    let status = response.status();
+    let problem = ProblemResponse {
+        code: error.code,
+        detail: error.message,
+        instance,
+        kind: format!("urn:wit:error:{}", error.code),
+        request_id: error.request_id,
+        status: status.as_u16(),
+        title: status.canonical_reason().unwrap_or_default(),
+    };
+    // Only the body changes, so headers such as `Retry-After` are kept.
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(header::CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON));
+    Response::from_parts(parts, Json(problem).into_response().into_body())
+}
+
+pub(super) fn deserialize_flag<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match String::deserialize(deserializer)?.as_str() {
+        "" | "0" | "false" => Ok(false),
+        "1" | "true" => Ok(true),
+        s => Err(serde::de::Error::invalid_value(
+            serde::de::Unexpected::Str(s),
+            &"one of 0, 1, false or true",
+        )),
+    }
 }
 
-pub(crate) fn router() -> Router<AppState> {
+pub(crate) fn router(state: &AppState) -> Router<AppState> {
     Router::new()
         .route("/statuses", get(gather_status))
-        .route("/blobs/{id}", get(get_blob))
+        .route(
+            "/stats",
+            get(gather_tree_stats).layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_expensive,
+            )),
+        )
+        .route(
+            "/blame",
+            get(blame_file).layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_expensive,
+            )),
+        )
+        .route("/blobs/batch", post(get_blob_batch))
+        .route("/blobs/{id}", get(get_blob).head(head_blob))
+        .route("/blobs/{id}/raw", get(get_raw_blob).head(head_blob))
         .route("/branches", get(list_branch))
+        .route("/branches/{name}/divergence", get(get_branch_divergence))
+        .route("/cherry", get(list_cherry))
         .route("/commits", get(list_commit))
+        .route("/commits/batch", post(get_commit_batch))
+        .route("/commits/unreleased", get(list_unreleased))
+        .route("/commits/{id}", get(get_commit).head(head_commit))
+        .route("/commits/{id}/branches", get(list_branch_containing))
+        .route("/commits/{id}/diff", get(diff_commit))
+        .route(
+            "/commits/{id}/revert",
+            post(revert_commit).layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_write_token,
+            )),
+        )
+        .route(
+            "/commits/{id}/tags",
+            get(list_tag_containing).layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_expensive,
+            )),
+        )
+        .route("/compare", get(compare))
+        .route(
+            "/default-branch",
+            get(get_default_branch).merge(put(override_default_branch).layer(
+                middleware::from_fn_with_state(state.clone(), require_write_token),
+            )),
+        )
+        .route(
+            "/merge",
+            post(merge).layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_write_token,
+            )),
+        )
+        .route("/owners", get(list_code_owner))
+        .route(
+            "/grep",
+            get(grep).layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_expensive,
+            )),
+        )
+        .route("/hooks/refresh", post(hooks::refresh))
         .route("/indexes", get(list_index))
+        .route("/license", get(list_license))
+        .route(
+            "/log",
+            get(search_commit).layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_expensive,
+            )),
+        )
+        .route("/objects/{id}", get(get_object).head(head_object))
+        .route("/objects/{id}/raw", get(get_raw_object).head(head_object))
+        .route("/packs", get(list_pack))
+        .route(
+            "/patches/apply",
+            post(apply_patch).layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_write_token,
+            )),
+        )
+        .route(
+            "/packs/{name}/verify",
+            post(verify_pack).layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_expensive,
+            )),
+        )
         .route("/references", get(list_reference))
         .route("/remotes", get(list_remote))
+        .route(
+            "/remotes/{name}/fetch",
+            post(fetch_remote).layer(middleware::from_fn_with_state(
+                state.clone(),
+                require_write_token,
+            )),
+        )
+        .route(
+            "/remotes/{name}/refs",
+            get(list_remote_ref).layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_expensive,
+            )),
+        )
+        .route(
+            "/search/code",
+            get(search_code).layer(middleware::from_fn_with_state(
+                state.clone(),
+                limit_expensive,
+            )),
+        )
+        .route("/search/paths", get(search_path))
         .route("/tags", get(list_tag))
         .route("/trees", get(list_tree))
+        .route("/trees/{*reference}", get(list_manifest).head(head_tree))
+        .layer(middleware::from_fn(select_fields))
+        .layer(middleware::from_fn(negotiate_problem))
+}
+
+#[derive(Debug, Deserialize)]
+struct ApplyPatchQuery {
+    branch: String,
 }
 
-async fn gather_status(State(state): State<AppState>) -> ApiResult<Json<Vec<GitStatus>>> {
-    Ok(Json(GitRepository::open(state.repo_root)?.gather_status()?))
+async fn apply_patch(
+    State(state): State<AppState>,
+    query: Result<Query<ApplyPatchQuery>, QueryRejection>,
+    mbox: String,
+) -> ApiResult<(StatusCode, Json<Vec<GitCommit>>)> {
+    let branch = query?.0.branch;
+    let commits = state
+        .repo
+        .spawn(move |repo| repo.apply_patch(&branch, &mbox))
+        .await?;
+    Ok((StatusCode::CREATED, Json(commits)))
+}
+
+#[derive(Debug, Deserialize)]
+struct BlameQuery {
+    path: String,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    raw_identity: bool,
+    #[serde(rename = "ref")]
+    reference: Option<String>,
+}
+
+type ETag = AppendHeaders<Option<(HeaderName, HeaderValue)>>;
+
+/// Content addressed by `id` never changes, so the id is a strong validator.
+fn etag(id: &GitOid) -> ETag {
+    AppendHeaders(
+        HeaderValue::from_str(&format!("\"{id}\""))
+            .ok()
+            .map(|v| (header::ETAG, v)),
+    )
+}
+
+async fn blame_file(
+    State(state): State<AppState>,
+    query: Result<Query<BlameQuery>, QueryRejection>,
+) -> ApiResult<Json<Vec<GitBlameHunk>>> {
+    let query = query?.0;
+    let reference = query.reference.unwrap_or_else(|| String::from("HEAD"));
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| {
+                let id = repo.resolve_commit(&reference)?;
+                state
+                    .blames
+                    .blame_file(repo, &id, &query.path, !query.raw_identity)
+            })
+            .await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct CompareQuery {
+    base: String,
+    head: String,
+    ignore_whitespace: Option<IgnoreWhitespace>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    word_diff: bool,
+}
+
+async fn compare(
+    State(state): State<AppState>,
+    query: Result<Query<CompareQuery>, QueryRejection>,
+) -> ApiResult<Json<Vec<GitDiffFile>>> {
+    let query = query?.0;
+    let options = DiffOptions {
+        ignore_whitespace: query.ignore_whitespace,
+        word_diff: query.word_diff,
+    };
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| repo.compare(&query.base, &query.head, options))
+            .await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct DiffQuery {
+    ignore_whitespace: Option<IgnoreWhitespace>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    word_diff: bool,
+}
+
+async fn diff_commit(
+    State(state): State<AppState>,
+    id: Result<Path<GitOid>, PathRejection>,
+    query: Result<Query<DiffQuery>, QueryRejection>,
+) -> ApiResult<Json<Vec<GitDiffFile>>> {
+    let id = id?.0;
+    let query = query?.0;
+    let options = DiffOptions {
+        ignore_whitespace: query.ignore_whitespace,
+        word_diff: query.word_diff,
+    };
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| repo.diff_commit(id, options))
+            .await?,
+    ))
+}
+
+async fn fetch_remote(
+    State(state): State<AppState>,
+    name: Result<Path<String>, PathRejection>,
+) -> ApiResult<StatusCode> {
+    let name = name?.0;
+    let config = state.config.clone();
+    let notify = state.mailer.is_some();
+    let mails = state
+        .repo
+        .spawn(move |repo| {
+            let updates = repo.fetch_remote(&name)?;
+            if !notify {
+                return Ok(vec![]);
+            }
+            Ok(repo.ref_update_mails(&config.notify, &updates))
+        })
+        .await?;
+    if let Some(mailer) = &state.mailer {
+        mailer.spawn_send(state.config.notify.recipients.clone(), mails);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+const REPOSITORY_STATE_HEADER: HeaderName = HeaderName::from_static("x-repository-state");
+
+/// The operation in progress, if any, is in the `x-repository-state` header
+/// since statuses differ while one is.
+async fn gather_status(
+    State(state): State<AppState>,
+    sort: Result<Query<SortOptions>, QueryRejection>,
+) -> ApiResult<([(HeaderName, &'static str); 1], Json<Vec<GitStatus>>)> {
+    let sort = sort?.0;
+    let (mut statuses, repo_state) = state
+        .repo
+        .spawn(|repo| Ok((repo.gather_status()?, repo.state())))
+        .await?;
+    sort.apply(&mut statuses);
+    Ok((
+        [(
+            REPOSITORY_STATE_HEADER,
+            repo_state.operation().unwrap_or("clean"),
+        )],
+        Json(statuses),
+    ))
+}
+
+async fn gather_tree_stats(State(state): State<AppState>) -> ApiResult<Json<GitTreeStats>> {
+    Ok(Json(
+        state.repo.spawn(GitRepository::gather_tree_stats).await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct GetBlobQuery {
+    path: Option<String>,
 }
 
 async fn get_blob(
     State(state): State<AppState>,
     id: Result<Path<GitOid>, PathRejection>,
-) -> ApiResult<Json<GitBlob>> {
+    query: Result<Query<GetBlobQuery>, QueryRejection>,
+) -> ApiResult<(ETag, Json<GitBlob>)> {
     let id = id?.0;
-    Ok(Json(GitRepository::open(state.repo_root)?.get_blob(id)?))
+    let query = query?.0;
+    let etag = etag(&id);
+    Ok((
+        etag,
+        Json(
+            state
+                .repo
+                .spawn(move |repo| {
+                    repo.get_blob(id, query.path.as_deref(), None, state.max_blob_size)
+                })
+                .await?,
+        ),
+    ))
+}
+
+async fn get_blob_batch(
+    State(state): State<AppState>,
+    specs: Result<Json<Vec<BlobSpec>>, JsonRejection>,
+) -> ApiResult<Json<Vec<BatchItem<GitBlob>>>> {
+    let specs = specs?.0;
+    let results = state
+        .repo
+        .spawn(move |repo| repo.get_blob_batch(specs, state.max_blob_size))
+        .await?;
+    Ok(Json(results.into_iter().map(Into::into).collect()))
+}
+
+#[derive(Debug, Deserialize)]
+struct GetCommitQuery {
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    raw_identity: bool,
+}
+
+async fn get_commit(
+    State(state): State<AppState>,
+    id: Result<Path<GitOid>, PathRejection>,
+    query: Result<Query<GetCommitQuery>, QueryRejection>,
+) -> ApiResult<(ETag, Json<GitCommitDetail>)> {
+    let id = id?.0;
+    let query = query?.0;
+    let etag = etag(&id);
+    Ok((
+        etag,
+        Json(
+            state
+                .repo
+                .spawn(move |repo| repo.get_commit(id, !query.raw_identity))
+                .await?,
+        ),
+    ))
+}
+
+async fn get_commit_batch(
+    State(state): State<AppState>,
+    query: Result<Query<GetCommitQuery>, QueryRejection>,
+    ids: Result<Json<Vec<GitOid>>, JsonRejection>,
+) -> ApiResult<Json<Vec<BatchItem<GitCommit>>>> {
+    let query = query?.0;
+    let ids = ids?.0;
+    let results = state
+        .repo
+        .spawn(move |repo| repo.get_commit_batch(ids, !query.raw_identity))
+        .await?;
+    Ok(Json(results.into_iter().map(Into::into).collect()))
+}
+
+async fn get_default_branch(State(state): State<AppState>) -> ApiResult<Json<GitDefaultBranch>> {
+    Ok(Json(state.repo.spawn(GitRepository::default_branch).await?))
+}
+
+async fn get_object(
+    State(state): State<AppState>,
+    id: Result<Path<GitOid>, PathRejection>,
+) -> ApiResult<(ETag, Json<GitObject>)> {
+    let id = id?.0;
+    let etag = etag(&id);
+    Ok((
+        etag,
+        Json(state.repo.spawn(move |repo| repo.get_object(id)).await?),
+    ))
+}
+
+async fn get_raw_blob(
+    State(state): State<AppState>,
+    id: Result<Path<GitOid>, PathRejection>,
+) -> ApiResult<impl IntoResponse> {
+    let id = id?.0;
+    let etag = etag(&id);
+    let content = state.repo.spawn(move |repo| repo.get_raw_blob(id)).await?;
+    Ok((
+        etag,
+        [
+            (header::CONTENT_TYPE, "application/octet-stream"),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        ],
+        content,
+    ))
 }
 
-async fn list_branch(State(state): State<AppState>) -> ApiResult<Json<Vec<GitBranch>>> {
-    Ok(Json(GitRepository::open(state.repo_root)?.list_branch()?))
+async fn get_raw_object(
+    State(state): State<AppState>,
+    id: Result<Path<GitOid>, PathRejection>,
+) -> ApiResult<impl IntoResponse> {
+    let id = id?.0;
+    let etag = etag(&id);
+    let content = state
+        .repo
+        .spawn(move |repo| repo.get_raw_object(id))
+        .await?;
+    Ok((
+        etag,
+        [
+            (header::CONTENT_TYPE, "application/octet-stream"),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        ],
+        content,
+    ))
 }
 
-async fn list_commit(State(state): State<AppState>) -> ApiResult<Json<Vec<GitCommit>>> {
-    Ok(Json(GitRepository::open(state.repo_root)?.list_commit()?))
+#[derive(Debug, Deserialize)]
+struct GrepQuery {
+    pattern: String,
+    #[serde(rename = "ref")]
+    reference: Option<String>,
+}
+
+async fn grep(
+    State(state): State<AppState>,
+    query: Result<Query<GrepQuery>, QueryRejection>,
+) -> ApiResult<Json<Vec<CodeMatch>>> {
+    let query = query?.0;
+    let reference = query.reference.unwrap_or_else(|| String::from("HEAD"));
+    Ok(Json(
+        search::grep(state.repo, reference, query.pattern).await?,
+    ))
+}
+
+async fn list_branch(
+    State(state): State<AppState>,
+    filter: Result<Query<ListBranchOptions>, QueryRejection>,
+    sort: Result<Query<SortOptions>, QueryRejection>,
+) -> ApiResult<Json<Vec<GitBranch>>> {
+    let filter = filter?.0;
+    let sort = sort?.0;
+    let mut branches = state
+        .repo
+        .spawn(move |repo| repo.list_branch_matching(&filter))
+        .await?;
+    sort.apply(&mut branches);
+    Ok(Json(branches))
+}
+
+async fn get_branch_divergence(
+    State(state): State<AppState>,
+    name: Result<Path<String>, PathRejection>,
+) -> ApiResult<Json<GitBranchDivergence>> {
+    let name = name?.0;
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| repo.branch_divergence(&name))
+            .await?,
+    ))
+}
+
+async fn list_branch_containing(
+    State(state): State<AppState>,
+    id: Result<Path<GitOid>, PathRejection>,
+) -> ApiResult<Json<Vec<GitBranch>>> {
+    let id = id?.0;
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| repo.list_branch_containing(id))
+            .await?,
+    ))
+}
+
+/// Answers HEAD requests for objects by id without reading their content.
+async fn head_object_of(
+    state: AppState,
+    id: Result<Path<GitOid>, PathRejection>,
+    kind: Option<GitObjectType>,
+) -> ApiResult<impl IntoResponse> {
+    let id = id?.0;
+    let etag = etag(&id);
+    state
+        .repo
+        .spawn(move |repo| repo.check_object(&id, kind))
+        .await?;
+    Ok(etag)
+}
+
+async fn head_blob(
+    State(state): State<AppState>,
+    id: Result<Path<GitOid>, PathRejection>,
+) -> ApiResult<impl IntoResponse> {
+    head_object_of(state, id, Some(GitObjectType::Blob)).await
+}
+
+async fn head_commit(
+    State(state): State<AppState>,
+    id: Result<Path<GitOid>, PathRejection>,
+) -> ApiResult<impl IntoResponse> {
+    head_object_of(state, id, Some(GitObjectType::Commit)).await
+}
+
+async fn head_object(
+    State(state): State<AppState>,
+    id: Result<Path<GitOid>, PathRejection>,
+) -> ApiResult<impl IntoResponse> {
+    head_object_of(state, id, None).await
+}
+
+/// `reference` may name a path as `ref:path`; the ETag is the id of the
+/// tree, or of the blob for a file.
+async fn head_tree(
+    State(state): State<AppState>,
+    reference: Result<Path<String>, PathRejection>,
+) -> ApiResult<impl IntoResponse> {
+    let reference = reference?.0;
+    let id = state
+        .repo
+        .spawn(move |repo| repo.resolve_tree(&reference))
+        .await?;
+    Ok(etag(&id))
+}
+
+#[derive(Debug, Deserialize)]
+struct ListCherryQuery {
+    head: String,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    raw_identity: bool,
+    upstream: String,
+}
+
+async fn list_cherry(
+    State(state): State<AppState>,
+    query: Result<Query<ListCherryQuery>, QueryRejection>,
+) -> ApiResult<Json<Vec<GitCherry>>> {
+    let query = query?.0;
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| repo.list_cherry(&query.upstream, &query.head, !query.raw_identity))
+            .await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct ListCodeOwnerQuery {
+    path: String,
+}
+
+async fn list_code_owner(
+    State(state): State<AppState>,
+    query: Result<Query<ListCodeOwnerQuery>, QueryRejection>,
+) -> ApiResult<Json<Vec<GitCodeOwner>>> {
+    let query = query?.0;
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| repo.list_code_owner(&query.path))
+            .await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct ListCommitQuery {
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    raw_identity: bool,
+    until_oid: Option<GitOid>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    with_stats: bool,
+}
+
+async fn list_commit(
+    State(state): State<AppState>,
+    query: Result<Query<ListCommitQuery>, QueryRejection>,
+) -> ApiResult<Json<Vec<GitCommit>>> {
+    let query = query?.0;
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| {
+                repo.list_commit(ListCommitOptions {
+                    mailmap: !query.raw_identity,
+                    until: query.until_oid,
+                    with_stats: query.with_stats,
+                    ..Default::default()
+                })
+            })
+            .await?,
+    ))
 }
 
 async fn list_index(State(state): State<AppState>) -> ApiResult<Json<Vec<GitIndex>>> {
     Ok(Json(
-        GitRepository::open(state.repo_root)?.list_index(Default::default())?,
+        state
+            .repo
+            .spawn(|repo| repo.list_index(Default::default()))
+            .await?,
     ))
 }
 
-async fn list_reference(State(state): State<AppState>) -> ApiResult<Json<Vec<GitReference>>> {
+async fn list_license(State(state): State<AppState>) -> ApiResult<Json<Vec<GitLicense>>> {
+    Ok(Json(state.repo.spawn(GitRepository::list_license).await?))
+}
+
+#[derive(Debug, Deserialize)]
+struct ListManifestQuery {
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    recursive: bool,
+}
+
+async fn list_manifest(
+    State(state): State<AppState>,
+    reference: Result<Path<String>, PathRejection>,
+    page: Result<Query<PageQuery>, QueryRejection>,
+    query: Result<Query<ListManifestQuery>, QueryRejection>,
+) -> ApiResult<Json<GitManifest>> {
+    let reference = reference?.0;
+    let page = page?.0;
+    let query = query?.0;
     Ok(Json(
-        GitRepository::open(state.repo_root)?.list_reference()?,
+        state
+            .repo
+            .spawn(move |repo| {
+                repo.list_manifest(
+                    &reference,
+                    query.recursive,
+                    page.offset(),
+                    page.limit.unwrap_or(MAX_MANIFEST_ENTRIES),
+                )
+            })
+            .await?,
     ))
 }
 
+async fn list_pack(State(state): State<AppState>) -> ApiResult<Json<Vec<GitPack>>> {
+    Ok(Json(state.repo.spawn(GitRepository::list_pack).await?))
+}
+
+async fn list_reference(
+    State(state): State<AppState>,
+    filter: Result<Query<ListReferenceOptions>, QueryRejection>,
+    sort: Result<Query<SortOptions>, QueryRejection>,
+) -> ApiResult<Json<Vec<GitReference>>> {
+    let filter = filter?.0;
+    let sort = sort?.0;
+    let mut references = state
+        .repo
+        .spawn(move |repo| repo.list_reference_matching(&filter))
+        .await?;
+    sort.apply(&mut references);
+    Ok(Json(references))
+}
+
 async fn list_remote(State(state): State<AppState>) -> ApiResult<Json<Vec<GitRemote>>> {
-    Ok(Json(GitRepository::open(state.repo_root)?.list_remote()?))
+    Ok(Json(state.repo.spawn(GitRepository::list_remote).await?))
+}
+
+async fn list_remote_ref(
+    State(state): State<AppState>,
+    name: Result<Path<String>, PathRejection>,
+) -> ApiResult<Json<Vec<GitRemoteRef>>> {
+    let name = name?.0;
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| repo.list_remote_ref(&name))
+            .await?,
+    ))
 }
 
-async fn list_tag(State(state): State<AppState>) -> ApiResult<Json<Vec<GitTag>>> {
-    Ok(Json(GitRepository::open(state.repo_root)?.list_tag()?))
+#[derive(Debug, Deserialize)]
+pub(super) struct ListTagQuery {
+    #[serde(rename = "match")]
+    pub(super) pattern: Option<String>,
+}
+
+async fn list_tag(
+    State(state): State<AppState>,
+    query: Result<Query<ListTagQuery>, QueryRejection>,
+    sort: Result<Query<SortOptions>, QueryRejection>,
+) -> ApiResult<Json<Vec<GitTag>>> {
+    let query = query?.0;
+    let sort = sort?.0;
+    let mut tags = state
+        .repo
+        .spawn(move |repo| repo.list_tag_matching(query.pattern.as_deref()))
+        .await?;
+    sort.apply(&mut tags);
+    Ok(Json(tags))
+}
+
+async fn list_tag_containing(
+    State(state): State<AppState>,
+    id: Result<Path<GitOid>, PathRejection>,
+) -> ApiResult<Json<GitCommitTags>> {
+    let id = id?.0;
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| repo.list_tag_containing(id))
+            .await?,
+    ))
 }
 
 async fn list_tree(State(state): State<AppState>) -> ApiResult<Json<Vec<GitTree>>> {
     Ok(Json(
-        GitRepository::open(state.repo_root)?.list_tree(Default::default())?,
+        state
+            .repo
+            .spawn(|repo| repo.list_tree(Default::default()))
+            .await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct ListUnreleasedQuery {
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    raw_identity: bool,
+    #[serde(rename = "ref")]
+    reference: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    with_stats: bool,
+}
+
+async fn list_unreleased(
+    State(state): State<AppState>,
+    query: Result<Query<ListUnreleasedQuery>, QueryRejection>,
+) -> ApiResult<Json<GitUnreleased>> {
+    let query = query?.0;
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| {
+                let start = match &query.reference {
+                    Some(reference) => Some(repo.resolve_commit(reference)?),
+                    None => None,
+                };
+                repo.list_unreleased(ListCommitOptions {
+                    mailmap: !query.raw_identity,
+                    start,
+                    with_stats: query.with_stats,
+                    ..Default::default()
+                })
+            })
+            .await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeBody {
+    base: String,
+    head: String,
+    #[serde(default)]
+    strategy: MergeStrategy,
+}
+
+/// Responds with 409 and the conflicting paths when the merge is not clean.
+async fn merge(
+    State(state): State<AppState>,
+    body: Result<Json<MergeBody>, JsonRejection>,
+) -> ApiResult<(StatusCode, Json<GitMergeResult>)> {
+    let body = body?.0;
+    let result = state
+        .repo
+        .spawn(move |repo| repo.merge(&body.base, &body.head, body.strategy))
+        .await?;
+    let status = match result.commit {
+        Some(_) => StatusCode::OK,
+        None => StatusCode::CONFLICT,
+    };
+    Ok((status, Json(result)))
+}
+
+#[derive(Debug, Deserialize)]
+struct DefaultBranchForm {
+    name: Option<String>,
+}
+
+/// An empty or missing `name` clears the override.
+async fn override_default_branch(
+    State(state): State<AppState>,
+    form: Result<Form<DefaultBranchForm>, FormRejection>,
+) -> ApiResult<Json<GitDefaultBranch>> {
+    let name = form?.0.name.filter(|n| !n.is_empty());
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| repo.override_default_branch(name))
+            .await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct RevertCommitQuery {
+    branch: String,
+    mainline: Option<u32>,
+}
+
+async fn revert_commit(
+    State(state): State<AppState>,
+    id: Result<Path<GitOid>, PathRejection>,
+    query: Result<Query<RevertCommitQuery>, QueryRejection>,
+) -> ApiResult<(StatusCode, Json<GitCommit>)> {
+    let id = id?.0;
+    let query = query?.0;
+    let commit = state
+        .repo
+        .spawn(move |repo| repo.revert_commit(id.0, &query.branch, query.mainline))
+        .await?;
+    Ok((StatusCode::CREATED, Json(commit)))
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchQuery {
+    q: String,
+}
+
+async fn search_code(
+    State(state): State<AppState>,
+    query: Result<Query<SearchQuery>, QueryRejection>,
+) -> ApiResult<Json<Vec<CodeMatch>>> {
+    let query = query?.0;
+    Ok(Json(
+        search::search_code(state.search, state.repo, query.q).await?,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchCommitQuery {
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    follow: bool,
+    path: Option<String>,
+    pickaxe: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    raw_identity: bool,
+}
+
+async fn search_commit(
+    State(state): State<AppState>,
+    query: Result<Query<SearchCommitQuery>, QueryRejection>,
+) -> ApiResult<Json<Vec<GitCommit>>> {
+    let query = query?.0;
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| {
+                repo.search_commit(SearchCommitOptions {
+                    follow: query.follow,
+                    mailmap: !query.raw_identity,
+                    path: query.path,
+                    pickaxe: query.pickaxe,
+                })
+            })
+            .await?,
+    ))
+}
+
+async fn search_path(
+    State(state): State<AppState>,
+    query: Result<Query<SearchQuery>, QueryRejection>,
+) -> ApiResult<Json<Vec<PathMatch>>> {
+    let query = query?.0;
+    Ok(Json(
+        search::search_path(state.paths, state.repo, query.q).await?,
     ))
 }
+
+async fn verify_pack(
+    State(state): State<AppState>,
+    name: Result<Path<String>, PathRejection>,
+) -> ApiResult<Json<GitPackVerification>> {
+    let name = name?.0;
+    Ok(Json(
+        state
+            .repo
+            .spawn(move |repo| repo.verify_pack(&name))
+            .await?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[test]
+    fn test_error_code_display() {
+        for code in [
+            ErrorCode::Internal,
+            ErrorCode::InvalidRef,
+            ErrorCode::RepositoryNotFound,
+        ] {
+            let json = serde_json::to_value(code)
+                .unwrap_or_else(|e| panic!("serialize {code:?} failed: {e:?}"));
+            assert_eq!(json, code.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_negotiate_problem() {
+        let app = Router::new()
+            .route(
+                "/fail",
+                get(|| async { ApiError::from(GitError::ObjectNotFound(String::from("x"))) }),
+            )
+            .layer(middleware::from_fn(negotiate_problem));
+        for (accept, content_type, kind) in [
+            ("application/json", "application/json", None),
+            (
+                "application/json;q=0.5, application/problem+json",
+                PROBLEM_JSON,
+                Some("urn:wit:error:object_not_found"),
+            ),
+        ] {
+            let request = Request::builder()
+                .uri("/fail")
+                .header(header::ACCEPT, accept)
+                .body(Body::empty())
+                .unwrap_or_else(|e| panic!("build request failed: {e:?}"));
+            let response = app
+                .clone()
+                .oneshot(request)
+                .await
+                .unwrap_or_else(|e| panic!("route request failed: {e:?}"));
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+            assert_eq!(
+                response.headers().get(header::CONTENT_TYPE),
+                Some(&HeaderValue::from_static(content_type))
+            );
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap_or_else(|e| panic!("read body failed: {e:?}"));
+            let body: serde_json::Value = serde_json::from_slice(&body)
+                .unwrap_or_else(|e| panic!("parse body failed: {e:?}"));
+            assert_eq!(body["code"], "object_not_found");
+            assert_eq!(body["type"].as_str(), kind);
+            if kind.is_some() {
+                assert_eq!(body["status"], 404);
+                assert_eq!(body["instance"], "/fail");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_overloaded_retry_after() {
+        let app = Router::new()
+            .route("/shed", get(|| async { ApiError::Overloaded(3) }))
+            .layer(middleware::from_fn(negotiate_problem));
+        for content_type in ["application/json", PROBLEM_JSON] {
+            let request = Request::builder()
+                .uri("/shed")
+                .header(header::ACCEPT, content_type)
+                .body(Body::empty())
+                .unwrap_or_else(|e| panic!("build request failed: {e:?}"));
+            let response = app
+                .clone()
+                .oneshot(request)
+                .await
+                .unwrap_or_else(|e| panic!("route request failed: {e:?}"));
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+            assert_eq!(
+                response.headers().get(header::CONTENT_TYPE),
+                Some(&HeaderValue::from_static(content_type))
+            );
+            assert_eq!(
+                response.headers().get(header::RETRY_AFTER),
+                Some(&HeaderValue::from(3)),
+                "{content_type}"
+            );
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use axum::{
+    extract::{rejection::QueryRejection, OriginalUri, Query, State},
+    http::Uri,
+    middleware,
+    routing::get,
+    Json, Router,
+};
+use git2::Oid;
+use serde::{Deserialize, Serialize};
+
+use crate::service::git::{
+    model::{GitBranch, GitCommit, GitOid, GitReference, GitRemote, GitTag, GitTree},
+    GitRepository, GitResult, ListBranchOptions, ListCommitOptions, ListReferenceOptions,
+};
+
+use super::{
+    api::{deserialize_flag, negotiate_problem, ApiResult, ListTagQuery},
+    fields::select_fields,
+    pagination::{with_query, PageQuery, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE},
+    AppState,
+};
+
+#[derive(Debug, Serialize)]
+struct Collection<T> {
+    data: Vec<T>,
+    links: Links,
+    pagination: Pagination,
+}
+
+#[derive(Debug, Serialize)]
+struct Links {
+    #[serde(rename = "self")]
+    current: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Pagination {
+    next_cursor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total: Option<usize>,
+}
+
+impl<T> Collection<T> {
+    fn new(uri: &Uri, data: Vec<T>, next_cursor: Option<String>, total: Option<usize>) -> Self {
+        Collection {
+            data,
+            links: Links {
+                current: uri.to_string(),
+                next: next_cursor
+                    .as_deref()
+                    .map(|cursor| with_query(uri, "cursor", cursor)),
+            },
+            pagination: Pagination { next_cursor, total },
+        }
+    }
+
+    fn paginate(uri: &Uri, mut items: Vec<T>, query: &PageQuery) -> Self {
+        let total = items.len();
+        let offset = query.offset().min(total);
+        let end = offset.saturating_add(query.limit()).min(total);
+        let next_cursor = (end < total).then(|| end.to_string());
+        items.truncate(end);
+        let data = items.drain(offset..).collect();
+        Collection::new(uri, data, next_cursor, Some(total))
+    }
+}
+
+pub(crate) fn router() -> Router<AppState> {
+    Router::new()
+        .route("/branches", get(list_branch))
+        .route("/commits", get(list_commit))
+        .route("/references", get(list_reference))
+        .route("/remotes", get(list_remote))
+        .route("/tags", get(list_tag))
+        .route("/trees", get(list_tree))
+        .layer(middleware::from_fn(select_fields))
+        .layer(middleware::from_fn(negotiate_problem))
+}
+
+async fn list_page<T, F>(
+    state: AppState,
+    uri: Uri,
+    query: Result<Query<PageQuery>, QueryRejection>,
+    f: F,
+) -> ApiResult<Json<Collection<T>>>
+where
+    T: Send + 'static,
+    F: FnOnce(&GitRepository) -> GitResult<Vec<T>> + Send + 'static,
+{
+    let query = query?.0;
+    let items = state.repo.spawn(f).await?;
+    Ok(Json(Collection::paginate(&uri, items, &query)))
+}
+
+async fn list_branch(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    query: Result<Query<PageQuery>, QueryRejection>,
+    filter: Result<Query<ListBranchOptions>, QueryRejection>,
+) -> ApiResult<Json<Collection<GitBranch>>> {
+    let filter = filter?.0;
+    list_page(state, uri, query, move |repo| {
+        repo.list_branch_matching(&filter)
+    })
+    .await
+}
+
+/// Where the next page of commits starts: the commit the first page was
+/// walked from and how many commits of that walk were already returned.
+/// Walking again from the original start keeps every parent of a merge,
+/// which restarting from the last commit shown would lose.
+#[derive(Debug, Deserialize)]
+#[serde(try_from = "String")]
+struct CommitCursor {
+    offset: usize,
+    start: GitOid,
+}
+
+impl std::fmt::Display for CommitCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.start, self.offset)
+    }
+}
+
+impl TryFrom<String> for CommitCursor {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let invalid = || format!("invalid commit cursor {s:?}");
+        let (start, offset) = s.split_once(':').ok_or_else(invalid)?;
+        Ok(CommitCursor {
+            offset: offset.parse().map_err(|_| invalid())?,
+            start: Oid::from_str(start).map_err(|_| invalid())?.into(),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListCommitQuery {
+    cursor: Option<CommitCursor>,
+    limit: Option<usize>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    raw_identity: bool,
+    until_oid: Option<GitOid>,
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    with_stats: bool,
+}
+
+async fn list_commit(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    query: Result<Query<ListCommitQuery>, QueryRejection>,
+) -> ApiResult<Json<Collection<GitCommit>>> {
+    let query = query?.0;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let (start, offset) = match query.cursor {
+        Some(cursor) => (Some(cursor.start), cursor.offset),
+        None => (None, 0),
+    };
+    let (start, (commits, has_more)) = state
+        .repo
+        .spawn(move |repo| {
+            let start = match start {
+                Some(start) => start,
+                None => repo.head_id()?,
+            };
+            let commits = repo.list_commit_page(ListCommitOptions {
+                limit,
+                mailmap: !query.raw_identity,
+                offset,
+                start: Some(start.clone()),
+                until: query.until_oid,
+                with_stats: query.with_stats,
+                ..Default::default()
+            })?;
+            Ok((start, commits))
+        })
+        .await?;
+    let next_cursor = has_more.then(|| {
+        CommitCursor {
+            offset: offset + commits.len(),
+            start,
+        }
+        .to_string()
+    });
+    Ok(Json(Collection::new(&uri, commits, next_cursor, None)))
+}
+
+async fn list_reference(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    query: Result<Query<PageQuery>, QueryRejection>,
+    filter: Result<Query<ListReferenceOptions>, QueryRejection>,
+) -> ApiResult<Json<Collection<GitReference>>> {
+    let filter = filter?.0;
+    list_page(state, uri, query, move |repo| {
+        repo.list_reference_matching(&filter)
+    })
+    .await
+}
+
+async fn list_remote(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    query: Result<Query<PageQuery>, QueryRejection>,
+) -> ApiResult<Json<Collection<GitRemote>>> {
+    list_page(state, uri, query, GitRepository::list_remote).await
+}
+
+async fn list_tag(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    query: Result<Query<PageQuery>, QueryRejection>,
+    filter: Result<Query<ListTagQuery>, QueryRejection>,
+) -> ApiResult<Json<Collection<GitTag>>> {
+    let filter = filter?.0;
+    list_page(state, uri, query, move |repo| {
+        repo.list_tag_matching(filter.pattern.as_deref())
+    })
+    .await
+}
+
+async fn list_tree(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    query: Result<Query<PageQuery>, QueryRejection>,
+) -> ApiResult<Json<Collection<GitTree>>> {
+    list_page(state, uri, query, |repo| repo.list_tree(Default::default())).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate() {
+        let uri: Uri = "/api/v2/git/tags?limit=2&cursor=2"
+            .parse()
+            .unwrap_or_else(|e| {
+                panic!("parse uri failed: {e:?}");
+            });
+        let query = PageQuery {
+            cursor: Some(2),
+            limit: Some(2),
+        };
+        let page = Collection::paginate(&uri, (0..5).collect(), &query);
+        assert_eq!(page.data, [2, 3]);
+        assert_eq!(page.pagination.next_cursor.as_deref(), Some("4"));
+        assert_eq!(page.pagination.total, Some(5));
+        assert_eq!(
+            page.links.next.as_deref(),
+            Some("/api/v2/git/tags?limit=2&cursor=4")
+        );
+
+        let query = PageQuery {
+            cursor: Some(4),
+            limit: Some(2),
+        };
+        let page = Collection::paginate(&uri, (0..5).collect(), &query);
+        assert_eq!(page.data, [4]);
+        assert_eq!(page.pagination.next_cursor, None);
+        assert_eq!(page.links.next, None);
+    }
+
+    #[test]
+    fn test_commit_cursor() {
+        let start = "8a6f2d5c0b1e3a4f7d9c2b6e1a0f3d5c7b9e2a4f";
+        let cursor = CommitCursor::try_from(format!("{start}:50"))
+            .unwrap_or_else(|e| panic!("parse commit cursor failed: {e}"));
+        assert_eq!(cursor.offset, 50);
+        assert_eq!(cursor.start.to_string(), start);
+        assert_eq!(cursor.to_string(), format!("{start}:50"));
+
+        for s in [start, "HEAD:1", "8a6f:x", ""] {
+            assert!(CommitCursor::try_from(String::from(s)).is_err(), "{s}");
+        }
+    }
+}
@@ -1,6 +1,76 @@
-use axum::Router;
-use tower_http::services::ServeDir;
+use axum::{
+    extract::Path,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+include!(concat!(env!("OUT_DIR"), "/assets.rs"));
+
+pub(crate) fn asset_url(name: &str) -> String {
+    let name = ASSETS
+        .iter()
+        .find(|(n, _, _)| *n == name)
+        .map_or(name, |(_, hashed, _)| hashed);
+    format!("/assets/{name}")
+}
 
 pub(crate) fn router() -> Router {
-    Router::new().nest_service("/assets", ServeDir::new("assets"))
+    Router::new().route("/assets/{*path}", get(get_asset))
+}
+
+async fn get_asset(Path(path): Path<String>) -> Response {
+    let asset = ASSETS
+        .iter()
+        .find(|(name, hashed, _)| *hashed == path || *name == path);
+    match asset {
+        Some((_, hashed, content)) => {
+            let mime = mime_guess::from_path(&path).first_or_octet_stream();
+            let cache_control = if *hashed == path {
+                IMMUTABLE_CACHE_CONTROL
+            } else {
+                "no-cache"
+            };
+            (
+                [
+                    (header::CONTENT_TYPE, mime.to_string()),
+                    (header::CACHE_CONTROL, cache_control.to_string()),
+                ],
+                *content,
+            )
+                .into_response()
+        }
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_asset() {
+        let response = get_asset(Path(String::from("missing.css"))).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(asset_url("missing.css"), "/assets/missing.css");
+
+        for (name, hashed, content) in ASSETS.iter() {
+            assert_eq!(asset_url(name), format!("/assets/{hashed}"));
+            for (path, cache_control) in [(name, "no-cache"), (hashed, IMMUTABLE_CACHE_CONTROL)] {
+                let response = get_asset(Path(path.to_string())).await;
+                assert_eq!(response.status(), StatusCode::OK);
+                assert_eq!(
+                    response.headers().get(header::CACHE_CONTROL),
+                    Some(&header::HeaderValue::from_static(cache_control))
+                );
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap_or_else(|e| panic!("read asset {path:?} failed: {e:?}"));
+                assert_eq!(&body[..], *content);
+            }
+        }
+    }
 }
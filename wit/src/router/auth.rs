@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+
+use super::{
+    api::{ApiError, ApiResult},
+    AppState,
+};
+
+/// Reads a bearer secret from the environment variable `name`. Empty
+/// values are ignored, which leaves the routes it guards rejecting every
+/// request.
+pub(super) fn secret_from_env(name: &str) -> Option<Arc<str>> {
+    let secret = std::env::var(name).ok()?;
+    if secret.is_empty() {
+        tracing::warn!("ignoring empty {name}");
+        return None;
+    }
+    Some(secret.into())
+}
+
+/// Compares in time independent of where the first mismatch is.
+fn secret_matches(given: &[u8], secret: &[u8]) -> bool {
+    given.len() == secret.len()
+        && given
+            .iter()
+            .zip(secret)
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// Checks the `Authorization: Bearer` header against `secret`.
+pub(super) fn authorize_bearer(headers: &HeaderMap, secret: Option<&str>) -> ApiResult<()> {
+    let given = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.as_bytes().strip_prefix(b"Bearer "));
+    match (given, secret) {
+        (Some(given), Some(secret)) if secret_matches(given, secret.as_bytes()) => Ok(()),
+        _ => Err(ApiError::Unauthorized),
+    }
+}
+
+/// Guards the routes that create commits or move branches. Without a token
+/// every write request is rejected.
+pub(super) async fn require_write_token(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> ApiResult<Response> {
+    authorize_bearer(request.headers(), state.write_token.as_deref())?;
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_matches() {
+        for (given, expected) in [
+            ("s3cret", true),
+            ("s3cre", false),
+            ("s3cres", false),
+            ("", false),
+        ] {
+            assert_eq!(
+                secret_matches(given.as_bytes(), b"s3cret"),
+                expected,
+                "{given}"
+            );
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use serde::Serialize;
+
+const DEFAULT_SITE_TITLE: &str = "wit";
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Branding {
+    pub(crate) accent_color: Option<String>,
+    pub(crate) footer_text: Option<String>,
+    pub(crate) logo_url: Option<String>,
+    pub(crate) title: String,
+}
+
+impl Default for Branding {
+    fn default() -> Self {
+        Branding {
+            accent_color: None,
+            footer_text: None,
+            logo_url: None,
+            title: String::from(DEFAULT_SITE_TITLE),
+        }
+    }
+}
+
+fn is_hex_color(s: &str) -> bool {
+    s.strip_prefix('#').is_some_and(|hex| {
+        matches!(hex.len(), 3 | 4 | 6 | 8) && hex.bytes().all(|b| b.is_ascii_hexdigit())
+    })
+}
+
+impl Branding {
+    pub(crate) fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().filter(|s| !s.trim().is_empty());
+        Branding {
+            accent_color: var("WIT_ACCENT_COLOR").filter(|color| {
+                is_hex_color(color) || {
+                    tracing::warn!("invalid accent color {color:?}, expected #rrggbb");
+                    false
+                }
+            }),
+            footer_text: var("WIT_FOOTER_TEXT"),
+            logo_url: var("WIT_LOGO_URL"),
+            title: var("WIT_SITE_TITLE").unwrap_or_else(|| String::from(DEFAULT_SITE_TITLE)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_hex_color() {
+        let sample = [
+            ("#fff", true),
+            ("#4f46e5", true),
+            ("#4f46e5cc", true),
+            ("4f46e5", false),
+            ("#4f46e", false),
+            ("#zzzzzz", false),
+            ("red;}body{", false),
+        ];
+        for (color, expected) in sample.into_iter() {
+            assert_eq!(is_hex_color(color), expected, "{color:?}");
+        }
+    }
+}
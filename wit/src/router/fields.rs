@@ -0,0 +1,104 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Query, Request},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Debug, Default, Deserialize)]
+struct FieldsQuery {
+    fields: Option<String>,
+}
+
+/// Trims successful JSON responses down to the top-level keys listed in
+/// `?fields=`. Arrays are filtered per item, and collection envelopes are
+/// filtered inside `data` so that their pagination metadata survives.
+pub(super) async fn select_fields(request: Request, next: Next) -> Response {
+    let query = Query::<FieldsQuery>::try_from_uri(request.uri())
+        .map(|q| q.0)
+        .unwrap_or_default();
+    let fields = parse_fields(query.fields.as_deref().unwrap_or_default());
+    let response = next.run(request).await;
+    if fields.is_empty() || !response.status().is_success() || !is_json(&response) {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let bytes = match to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            tracing::error!("read response body failed: {e:?}");
+            return parts.status.into_response();
+        }
+    };
+    let Ok(mut value) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    let is_collection = value_has_key(&value, "pagination");
+    match value.get_mut("data") {
+        Some(data) if is_collection => retain_fields(data, &fields),
+        _ => retain_fields(&mut value, &fields),
+    }
+    parts.headers.remove(header::CONTENT_LENGTH);
+    match serde_json::to_vec(&value) {
+        Ok(bytes) => Response::from_parts(parts, Body::from(bytes)),
+        Err(e) => {
+            tracing::error!("serialize response body failed: {e:?}");
+            parts.status.into_response()
+        }
+    }
+}
+
+fn parse_fields(fields: &str) -> Vec<String> {
+    fields
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn is_json(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"))
+}
+
+fn value_has_key(value: &Value, key: &str) -> bool {
+    value.as_object().is_some_and(|o| o.contains_key(key))
+}
+
+fn retain_fields(value: &mut Value, fields: &[String]) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|v| retain_fields(v, fields)),
+        Value::Object(object) => object.retain(|k, _| fields.iter().any(|f| f == k)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_retain_fields() {
+        let fields = parse_fields("id, message,,short_id");
+        assert_eq!(fields, ["id", "message", "short_id"]);
+
+        let mut value = json!([
+            {"id": "a", "short_id": "a", "message": "m", "author": {"name": "n"}},
+            {"id": "b", "summary": "s"},
+        ]);
+        retain_fields(&mut value, &fields);
+        assert_eq!(
+            value,
+            json!([{"id": "a", "short_id": "a", "message": "m"}, {"id": "b"}])
+        );
+    }
+}
@@ -1,19 +1,111 @@
-use axum::{http::StatusCode, routing::get, Router};
+use std::path::Path;
 
-pub(crate) fn router() -> Router {
-    Router::new().route("/healthz", get(health))
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Serialize;
+
+use crate::service::git::libgit2_version;
+
+use super::AppState;
+
+#[derive(Debug, Serialize)]
+struct DeepHealth {
+    caches: CacheHealth,
+    checks: Vec<Check>,
+    disk_available_bytes: Option<u64>,
+    libgit2_version: String,
+    status: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct CacheHealth {
+    blames: CacheUsage,
+    search_indexed_head: Option<String>,
+    summaries: CacheUsage,
+}
+
+#[derive(Debug, Serialize)]
+struct CacheUsage {
+    capacity: usize,
+    entries: usize,
+}
+
+impl From<(usize, usize)> for CacheUsage {
+    fn from((entries, capacity): (usize, usize)) -> Self {
+        CacheUsage { capacity, entries }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    name: &'static str,
+    ok: bool,
+}
+
+impl Check {
+    fn new<E: std::fmt::Display>(name: &'static str, result: Result<(), E>) -> Self {
+        let error = result.err().map(|e| e.to_string());
+        Check {
+            ok: error.is_none(),
+            error,
+            name,
+        }
+    }
+}
+
+pub(crate) fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/healthz", get(health))
+        .route("/healthz/deep", get(deep_health))
+        .with_state(state)
 }
 
 async fn health() -> StatusCode {
     StatusCode::OK
 }
 
+async fn deep_health(State(state): State<AppState>) -> Response {
+    let root = Path::new(&state.repo.root);
+    let checks = vec![
+        Check::new("repo_root", std::fs::read_dir(root).map(drop)),
+        Check::new("repository", state.repo.clone().spawn(|_| Ok(())).await),
+    ];
+    let search_indexed_head = state
+        .search
+        .as_ref()
+        .and_then(|s| s.indexed_head().ok())
+        .flatten();
+    let healthy = checks.iter().all(|c| c.ok);
+    let body = DeepHealth {
+        caches: CacheHealth {
+            blames: state.blames.usage().into(),
+            search_indexed_head,
+            summaries: state.summaries.usage().into(),
+        },
+        checks,
+        disk_available_bytes: fs4::available_space(root).ok(),
+        libgit2_version: libgit2_version(),
+        status: if healthy { "ok" } else { "degraded" },
+    };
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(body)).into_response()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use axum::response::IntoResponse;
-
     #[tokio::test]
     async fn test_health() {
         let response = health().await.into_response();
@@ -0,0 +1,29 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+
+use crate::service::task;
+
+use super::{api::ApiResult, auth, AppState};
+
+/// Called by push systems after a push lands elsewhere. Drops the caches and
+/// starts re-indexing search in the background instead of waiting for the
+/// next indexer tick.
+pub(super) async fn refresh(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<StatusCode> {
+    auth::authorize_bearer(&headers, state.hook_secret.as_deref())?;
+    state.blames.clear();
+    state.paths.clear();
+    state.summaries.clear();
+    if let Some(search) = state.search {
+        task::spawn_blocking(move || match search.refresh() {
+            Ok(true) => tracing::info!("search index refreshed by hook"),
+            Ok(false) => {}
+            Err(e) => tracing::warn!("failed to refresh search index by hook: {e}"),
+        });
+    }
+    Ok(StatusCode::ACCEPTED)
+}
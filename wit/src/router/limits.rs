@@ -0,0 +1,95 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tokio::sync::Semaphore;
+
+use super::{api::ApiError, AppState};
+
+pub(super) const DEFAULT_MAX_CONCURRENCY: usize = 512;
+pub(super) const DEFAULT_MAX_EXPENSIVE_CONCURRENCY: usize = 16;
+const MAX_RETRY_AFTER: u64 = 60;
+const SHED_THRESHOLD_FACTOR: usize = 4;
+
+#[derive(Debug)]
+pub(super) struct ExpensiveLimit {
+    in_flight: AtomicUsize,
+    max: usize,
+    permits: Semaphore,
+    shed_threshold: usize,
+}
+
+impl ExpensiveLimit {
+    /// `shed_threshold` defaults to four times `max`.
+    pub(super) fn new(max: usize, shed_threshold: Option<usize>) -> Self {
+        ExpensiveLimit {
+            in_flight: AtomicUsize::new(0),
+            max,
+            permits: Semaphore::new(max),
+            shed_threshold: shed_threshold.unwrap_or(max * SHED_THRESHOLD_FACTOR),
+        }
+    }
+
+    /// Roughly the number of rounds of expensive work queued ahead.
+    fn retry_after(&self, in_flight: usize) -> u64 {
+        u64::try_from(in_flight.div_ceil(self.max))
+            .unwrap_or(MAX_RETRY_AFTER)
+            .clamp(1, MAX_RETRY_AFTER)
+    }
+}
+
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+pub(super) fn limit_from_env(name: &str) -> Option<usize> {
+    std::env::var(name).ok().and_then(|s| {
+        s.parse::<usize>().ok().filter(|&n| n > 0).or_else(|| {
+            tracing::warn!("invalid concurrency limit {name}={s:?}");
+            None
+        })
+    })
+}
+
+/// Shared by archive, blame, commit log search, grep, pack verification,
+/// remote ref listing, stats, tags containing a commit and code search
+/// routes, so that together they run at most `WIT_MAX_EXPENSIVE_CONCURRENCY`
+/// requests. Once more than `WIT_SHED_THRESHOLD` are running or queued,
+/// further requests are rejected with 503 instead of waiting.
+pub(super) async fn limit_expensive(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let limit = &state.expensive;
+    let in_flight = limit.in_flight.fetch_add(1, Ordering::AcqRel) + 1;
+    let _in_flight = InFlight(&limit.in_flight);
+    if in_flight > limit.shed_threshold {
+        return ApiError::Overloaded(limit.retry_after(in_flight)).into_response();
+    }
+    match limit.permits.acquire().await {
+        Ok(_permit) => next.run(request).await,
+        Err(_) => ApiError::Overloaded(limit.retry_after(in_flight)).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_after() {
+        let limit = ExpensiveLimit::new(4, None);
+        assert_eq!(limit.shed_threshold, 16);
+        for (in_flight, expected) in [(1, 1), (17, 5), (10_000, MAX_RETRY_AFTER)] {
+            assert_eq!(limit.retry_after(in_flight), expected, "{in_flight}");
+        }
+    }
+}
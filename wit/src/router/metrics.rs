@@ -0,0 +1,24 @@
+use axum::{
+    http::{header, HeaderValue},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+
+use crate::service::git::render_metrics;
+
+const PROMETHEUS_TEXT: &str = "text/plain; version=0.0.4";
+
+pub(crate) fn router() -> Router {
+    Router::new().route("/metrics", get(metrics))
+}
+
+async fn metrics() -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(PROMETHEUS_TEXT),
+        )],
+        render_metrics(),
+    )
+}
@@ -1,47 +1,171 @@
 mod api;
+mod api_v2;
 mod assets;
+mod auth;
+mod branding;
+mod fields;
 mod front;
 mod git;
 mod health_check;
+mod hooks;
+mod limits;
+mod metrics;
+mod overrides;
+mod pagination;
+mod panic;
+mod prefs;
+mod profile;
+mod recent;
+mod request_id;
 
-use std::{iter::once, time::Duration};
+use std::{iter::once, sync::Arc, time::Duration};
 
-use axum::{http::header, Router};
-use tower::ServiceBuilder;
+use axum::{
+    extract::Request,
+    http::header,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use tower::{limit::GlobalConcurrencyLimitLayer, ServiceBuilder};
 use tower_http::{
     catch_panic::CatchPanicLayer,
     compression::CompressionLayer,
     cors::CorsLayer,
     request_id::MakeRequestUuid,
     sensitive_headers::{SetSensitiveRequestHeadersLayer, SetSensitiveResponseHeadersLayer},
-    timeout::TimeoutLayer,
     trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
     LatencyUnit, ServiceBuilderExt,
 };
 
+use crate::service::{
+    git::{BlameCache, GitRepository, GitSettings, RepoConfig, RepoHandle, SummaryCache},
+    mail::Mailer,
+    search::{PathFinder, SearchIndex},
+};
+
+pub(crate) use panic::install_panic_hook;
+
+const DEFAULT_MAX_BLOB_SIZE: usize = 1024 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Clone)]
 struct AppState {
-    repo_root: String,
+    blames: BlameCache,
+    branding: Arc<branding::Branding>,
+    config: Arc<RepoConfig>,
+    expensive: Arc<limits::ExpensiveLimit>,
+    hook_secret: Option<Arc<str>>,
+    mailer: Option<Mailer>,
+    max_blob_size: usize,
+    overrides: Arc<overrides::TemplateOverrides>,
+    paths: PathFinder,
+    repo: RepoHandle,
+    search: Option<SearchIndex>,
+    summaries: SummaryCache,
+    write_token: Option<Arc<str>>,
 }
 
 pub(crate) fn create_app() -> Router {
+    let repo_root = std::env::var("WIT_REPO_ROOT").unwrap_or(String::from("."));
+    let mut settings = GitSettings {
+        avatar_url_template: std::env::var("WIT_AVATAR_URL").ok(),
+        ..Default::default()
+    };
+
+    let max_blob_size = std::env::var("WIT_MAX_BLOB_SIZE")
+        .ok()
+        .and_then(|s| {
+            s.parse::<usize>()
+                .inspect_err(|_| tracing::warn!("invalid max blob size {s:?}"))
+                .ok()
+        })
+        .unwrap_or(DEFAULT_MAX_BLOB_SIZE);
+
+    if let Ok(s) = std::env::var("WIT_MEMORY_BUDGET") {
+        match s.parse::<usize>() {
+            Ok(bytes) => settings.memory_budget = bytes,
+            Err(_) => tracing::warn!("invalid memory budget {s:?}"),
+        }
+    }
+
+    let mailer = std::env::var("WIT_SMTP_RELAY").ok().and_then(|relay| {
+        let from = std::env::var("WIT_SMTP_FROM").unwrap_or(String::from("wit@localhost"));
+        Mailer::new(&relay, &from)
+            .inspect_err(|e| tracing::error!("invalid WIT_SMTP_RELAY or WIT_SMTP_FROM: {e}"))
+            .ok()
+    });
+
+    if let Some(seconds) = limits::limit_from_env("WIT_GIT_TIMEOUT") {
+        settings.git_timeout = Duration::from_secs(seconds as u64);
+    }
+    // SAFETY: no repository has been opened yet, so nothing else uses libgit2.
+    if let Err(e) = unsafe { settings.apply_network_timeout() } {
+        tracing::warn!("failed to set the git network timeout: {e}");
+    }
+
+    let config = GitRepository::open(&repo_root)
+        .map(|repo| repo.load_config())
+        .unwrap_or_default();
+    settings.default_branch = config.default_branch.clone();
+    let repo = RepoHandle::new(repo_root, settings);
+
+    let expensive = limits::ExpensiveLimit::new(
+        limits::limit_from_env("WIT_MAX_EXPENSIVE_CONCURRENCY")
+            .unwrap_or(limits::DEFAULT_MAX_EXPENSIVE_CONCURRENCY),
+        limits::limit_from_env("WIT_SHED_THRESHOLD"),
+    );
+    let max_concurrency =
+        limits::limit_from_env("WIT_MAX_CONCURRENCY").unwrap_or(limits::DEFAULT_MAX_CONCURRENCY);
+
+    let search = std::env::var("WIT_DATA_DIR").ok().and_then(|data_dir| {
+        match SearchIndex::open(&data_dir, repo.clone()) {
+            Ok(search) => {
+                search.spawn_indexer();
+                Some(search)
+            }
+            Err(e) => {
+                tracing::error!("failed to open search index in {data_dir:?}: {e}");
+                None
+            }
+        }
+    });
+
     let state = AppState {
-        repo_root: std::env::var("WIT_REPO_ROOT").unwrap_or(String::from(".")),
+        blames: BlameCache::default(),
+        branding: Arc::new(branding::Branding::from_env()),
+        config: Arc::new(config),
+        expensive: Arc::new(expensive),
+        hook_secret: auth::secret_from_env("WIT_HOOK_SECRET"),
+        mailer,
+        max_blob_size,
+        overrides: Arc::new(
+            std::env::var("WIT_TEMPLATE_DIR")
+                .map(|dir| overrides::TemplateOverrides::load(std::path::Path::new(&dir)))
+                .unwrap_or_default(),
+        ),
+        paths: PathFinder::default(),
+        repo,
+        search,
+        summaries: SummaryCache::default(),
+        write_token: auth::secret_from_env("WIT_WRITE_TOKEN"),
     };
 
     Router::new()
-        .nest("/api/v1", Router::new().nest("/git", api::router()))
-        .nest("/git", git::router())
-        .with_state(state)
+        .nest("/api/v1", Router::new().nest("/git", api::router(&state)))
+        .nest("/api/v2", Router::new().nest("/git", api_v2::router()))
+        .nest("/git", git::router(&state))
+        .with_state(state.clone())
         .layer(
             ServiceBuilder::new()
-                .layer(CatchPanicLayer::new())
                 .layer(SetSensitiveRequestHeadersLayer::new([
                     header::AUTHORIZATION,
                     header::COOKIE,
                     header::PROXY_AUTHORIZATION,
                 ]))
                 .set_x_request_id(MakeRequestUuid)
+                .layer(middleware::from_fn(request_id::scope_request_id))
+                .layer(CatchPanicLayer::custom(panic::api_panic))
                 .layer(
                     TraceLayer::new_for_http()
                         .make_span_with(DefaultMakeSpan::new().include_headers(true))
@@ -57,14 +181,68 @@ pub(crate) fn create_app() -> Router {
                 )))
                 .layer(CompressionLayer::new())
                 .layer(CorsLayer::permissive())
-                .layer(TimeoutLayer::new(Duration::from_secs(30))),
+                .layer(middleware::from_fn(timeout_request))
+                .layer(GlobalConcurrencyLimitLayer::new(max_concurrency)),
         )
         .merge(assets::router())
         .merge(front::router())
+        .merge(prefs::router())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            git::render_error_page,
+        ))
         .layer(
             ServiceBuilder::new()
                 .layer(CompressionLayer::new())
                 .layer(CorsLayer::permissive()),
         )
-        .merge(health_check::router())
+        .merge(profile::router(state.clone()))
+        .merge(health_check::router(state))
+        .merge(metrics::router())
+}
+
+/// Pages, and clients asking for HTML, get the timeout as an error page
+/// rather than JSON.
+fn wants_page(request: &Request) -> bool {
+    request.uri().path().starts_with("/git/")
+        || request.uri().path() == "/git"
+        || api::accepts(request.headers(), "text/html")
+}
+
+async fn timeout_request(request: Request, next: Next) -> Response {
+    let wants_page = wants_page(&request);
+    match tokio::time::timeout(REQUEST_TIMEOUT, next.run(request)).await {
+        Ok(response) => response,
+        Err(_) if wants_page => git::RenderError::ApiError(api::ApiError::Timeout).into_response(),
+        Err(_) => api::ApiError::Timeout.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+
+    use super::*;
+
+    #[test]
+    fn test_wants_page() {
+        let sample = [
+            ("/git/commits", None, true),
+            ("/git", None, true),
+            ("/api/v1/git/commits", None, false),
+            ("/api/v1/git/commits", Some("application/json"), false),
+            ("/api/v1/git/commits", Some("text/html,*/*;q=0.8"), true),
+            ("/gitx", None, false),
+        ];
+        for (uri, accept, expected) in sample.into_iter() {
+            let mut request = Request::builder().uri(uri);
+            if let Some(accept) = accept {
+                request = request.header(header::ACCEPT, accept);
+            }
+            let request = request
+                .body(Body::empty())
+                .unwrap_or_else(|e| panic!("build request failed: {e:?}"));
+            assert_eq!(wants_page(&request), expected, "{uri} {accept:?}");
+        }
+    }
 }
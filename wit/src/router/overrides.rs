@@ -0,0 +1,144 @@
+use std::{collections::HashMap, path::Path};
+
+use minijinja::Environment;
+use serde::Serialize;
+
+const TEMPLATE_SLOTS: [&str; 3] = ["footer", "head", "header"];
+
+/// Templates read from `WIT_TEMPLATE_DIR` at startup.
+///
+/// `footer.html`, `head.html` and `header.html` are snippets inserted as is
+/// into every embedded page. Any other `*.html` file is a minijinja template;
+/// one named like an embedded page, such as `repo-home.html`, replaces that
+/// page and is rendered from the same data, and the rest can be included or
+/// extended from those.
+#[derive(Debug)]
+pub(crate) struct TemplateOverrides {
+    pages: Environment<'static>,
+    snippets: HashMap<&'static str, String>,
+}
+
+impl Default for TemplateOverrides {
+    fn default() -> Self {
+        // Unlike `Environment::default`, with the builtin filters and HTML
+        // escaping for `.html` templates.
+        TemplateOverrides {
+            pages: Environment::new(),
+            snippets: HashMap::new(),
+        }
+    }
+}
+
+impl TemplateOverrides {
+    pub(crate) fn load(dir: &Path) -> Self {
+        let mut overrides = TemplateOverrides::default();
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                tracing::warn!("failed to read template overrides from {dir:?}: {e}");
+                return overrides;
+            }
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .filter(|name| name.ends_with(".html"))
+            else {
+                continue;
+            };
+            let content = match std::fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) => {
+                    tracing::warn!("failed to read template override {path:?}: {e}");
+                    continue;
+                }
+            };
+            match TEMPLATE_SLOTS
+                .into_iter()
+                .find(|slot| name.strip_suffix(".html") == Some(slot))
+            {
+                Some(slot) => {
+                    overrides.snippets.insert(slot, content);
+                }
+                None => {
+                    if let Err(e) = overrides
+                        .pages
+                        .add_template_owned(name.to_string(), content)
+                    {
+                        tracing::warn!("ignoring template override {path:?}: {e:#}");
+                    }
+                }
+            }
+        }
+        tracing::info!(
+            "loaded {} template overrides and {} snippets from {dir:?}",
+            overrides.pages.templates().count(),
+            overrides.snippets.len()
+        );
+        overrides
+    }
+
+    pub(crate) fn snippet(&self, slot: &str) -> &str {
+        self.snippets.get(slot).map_or("", String::as_str)
+    }
+
+    /// `None` when no override replaces the page `name`.
+    pub(super) fn render_page<S: Serialize>(
+        &self,
+        name: &str,
+        context: &S,
+    ) -> Option<Result<String, minijinja::Error>> {
+        let template = self.pages.get_template(name).ok()?;
+        Some(template.render(context))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::Write};
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Context {
+        title: &'static str,
+    }
+
+    #[test]
+    fn test_load_overrides() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        for (name, content) in [
+            ("footer.html", "<p>footer</p>"),
+            ("layout.html", "<h1>{% block title %}{% endblock %}</h1>"),
+            (
+                "repo-home.html",
+                "{% extends \"layout.html\" %}{% block title %}{{ title }}{% endblock %}",
+            ),
+            ("broken.html", "{% if %}"),
+            ("notes.txt", "{{ title }}"),
+        ] {
+            let mut file = File::create(path.join(name))
+                .unwrap_or_else(|e| panic!("create file failed: {e:?}"));
+            write!(file, "{content}").unwrap_or_else(|e| panic!("write file failed: {e:?}"));
+        }
+
+        let overrides = TemplateOverrides::load(path);
+        assert_eq!(overrides.snippet("footer"), "<p>footer</p>");
+        assert_eq!(overrides.snippet("header"), "");
+
+        let context = Context { title: "<wit>" };
+        let page = overrides
+            .render_page("repo-home.html", &context)
+            .unwrap_or_else(|| panic!("repo-home.html is not overridden"))
+            .unwrap_or_else(|e| panic!("render failed: {e:?}"));
+        assert_eq!(page, "<h1>&lt;wit&gt;</h1>");
+        assert!(overrides.render_page("repo-tags.html", &context).is_none());
+        assert!(overrides.render_page("broken.html", &context).is_none());
+        assert!(overrides.render_page("footer.html", &context).is_none());
+    }
+}
@@ -0,0 +1,129 @@
+use axum::http::Uri;
+use serde::{Deserialize, Serialize};
+
+pub(super) const DEFAULT_PAGE_SIZE: usize = 100;
+pub(super) const MAX_PAGE_SIZE: usize = 1000;
+const PAGE_SIZES: [usize; 3] = [25, 50, 100];
+
+#[derive(Debug, Default, Deserialize)]
+pub(super) struct PageQuery {
+    pub(super) cursor: Option<usize>,
+    pub(super) limit: Option<usize>,
+}
+
+impl PageQuery {
+    pub(super) fn limit(&self) -> usize {
+        self.limit_or(DEFAULT_PAGE_SIZE)
+    }
+
+    pub(super) fn limit_or(&self, default: usize) -> usize {
+        self.limit.unwrap_or(default).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub(super) fn offset(&self) -> usize {
+        self.cursor.unwrap_or_default()
+    }
+}
+
+/// Links rendered by `components/pagination.html`, keeping every other query
+/// parameter of the current page intact.
+#[derive(Debug, Serialize)]
+pub(super) struct Pager {
+    pub(super) end: usize,
+    pub(super) limit: usize,
+    pub(super) next: Option<String>,
+    pub(super) previous: Option<String>,
+    pub(super) sizes: Vec<(usize, String)>,
+    pub(super) start: usize,
+    pub(super) total: Option<usize>,
+}
+
+impl Pager {
+    pub(super) fn new(
+        uri: &Uri,
+        offset: usize,
+        limit: usize,
+        count: usize,
+        has_next: bool,
+        total: Option<usize>,
+    ) -> Self {
+        Pager {
+            end: offset + count,
+            limit,
+            next: has_next.then(|| with_query(uri, "cursor", &(offset + limit).to_string())),
+            previous: (offset > 0)
+                .then(|| with_query(uri, "cursor", &offset.saturating_sub(limit).to_string())),
+            sizes: PAGE_SIZES
+                .into_iter()
+                .map(|size| (size, with_query(uri, "limit", &size.to_string())))
+                .collect(),
+            start: if count == 0 { offset } else { offset + 1 },
+            total,
+        }
+    }
+
+    pub(super) fn is_limit(&self, size: &usize) -> bool {
+        self.limit == *size
+    }
+
+    /// Keeps `[offset, offset + limit)` of `items` and reports whether more
+    /// entries follow.
+    pub(super) fn paginate<T>(
+        uri: &Uri,
+        items: &mut Vec<T>,
+        query: &PageQuery,
+        default_limit: usize,
+    ) -> Self {
+        let total = items.len();
+        let limit = query.limit_or(default_limit);
+        let offset = query.offset().min(total);
+        let end = offset.saturating_add(limit).min(total);
+        items.truncate(end);
+        items.drain(..offset);
+        Pager::new(uri, offset, limit, end - offset, end < total, Some(total))
+    }
+}
+
+pub(super) fn with_query(uri: &Uri, key: &str, value: &str) -> String {
+    let prefix = format!("{key}=");
+    let mut query: Vec<_> = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !pair.starts_with(&prefix))
+        .collect();
+    let pair = format!("{prefix}{value}");
+    query.push(&pair);
+    format!("{}?{}", uri.path(), query.join("&"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate() {
+        let uri: Uri = "/git/tags?sort=name&cursor=2&limit=2"
+            .parse()
+            .unwrap_or_else(|e| {
+                panic!("parse uri failed: {e:?}");
+            });
+        let query = PageQuery {
+            cursor: Some(2),
+            limit: Some(2),
+        };
+        let mut items: Vec<_> = (0..5).collect();
+        let pager = Pager::paginate(&uri, &mut items, &query, DEFAULT_PAGE_SIZE);
+        assert_eq!(items, [2, 3]);
+        assert_eq!((pager.start, pager.end, pager.total), (3, 4, Some(5)));
+        assert_eq!(
+            pager.next.as_deref(),
+            Some("/git/tags?sort=name&limit=2&cursor=4")
+        );
+        assert_eq!(
+            pager.previous.as_deref(),
+            Some("/git/tags?sort=name&limit=2&cursor=0")
+        );
+        assert_eq!(pager.sizes[0].1, "/git/tags?sort=name&cursor=2&limit=25");
+    }
+}
@@ -0,0 +1,67 @@
+use std::{any::Any, backtrace::Backtrace};
+
+use axum::response::{IntoResponse, Response};
+
+use super::{api::ApiError, git::RenderError, request_id::current_request_id};
+
+/// Logs panics through tracing with a backtrace and the id of the request
+/// being handled, instead of printing them to stderr.
+pub(crate) fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let backtrace = Backtrace::force_capture();
+        match current_request_id() {
+            Some(request_id) => tracing::error!(request_id, "{info}\n{backtrace}"),
+            None => tracing::error!("{info}\n{backtrace}"),
+        }
+    }));
+}
+
+/// The payload is already logged by the panic hook and never sent to clients.
+pub(super) fn api_panic(_: Box<dyn Any + Send>) -> Response {
+    ApiError::Panic.into_response()
+}
+
+pub(super) fn page_panic(_: Box<dyn Any + Send>) -> Response {
+    RenderError::ApiError(ApiError::Panic).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        extract::Request,
+        http::{header, StatusCode},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+    use tower_http::catch_panic::CatchPanicLayer;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_api_panic() {
+        let app = Router::new()
+            .route("/", get(|| async { panic!("secret") as &'static str }))
+            .layer(CatchPanicLayer::custom(api_panic));
+        let request = Request::builder()
+            .uri("/")
+            .body(Body::empty())
+            .unwrap_or_else(|e| panic!("build request failed: {e:?}"));
+        let response = app
+            .oneshot(request)
+            .await
+            .unwrap_or_else(|e| panic!("route request failed: {e:?}"));
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            response.headers().get(header::CONTENT_TYPE),
+            Some(&header::HeaderValue::from_static("application/json"))
+        );
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap_or_else(|e| panic!("read body failed: {e:?}"));
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("\"code\":\"internal\""), "{body}");
+        assert!(!body.contains("secret"), "{body}");
+    }
+}
@@ -0,0 +1,200 @@
+use std::{convert::Infallible, sync::Arc};
+
+use axum::{
+    extract::{rejection::FormRejection, FromRequestParts, Query},
+    http::{header, request::Parts, HeaderMap, HeaderValue, Uri},
+    response::{IntoResponse, Redirect, Response},
+    routing::post,
+    Form, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    api::deserialize_flag, branding::Branding, git::RenderResult, overrides::TemplateOverrides,
+    AppState,
+};
+
+const PREFS_COOKIE_MAX_AGE: u64 = 365 * 24 * 60 * 60;
+const THEME_COOKIE: &str = "wit_theme";
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Theme {
+    Dark,
+    Light,
+    #[default]
+    System,
+}
+
+impl Theme {
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        match self {
+            Theme::Dark => Some("dark"),
+            Theme::Light => Some("light"),
+            Theme::System => None,
+        }
+    }
+}
+
+/// `fragment` drops the base layout so that pages can be loaded
+/// progressively into an existing document.
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct Prefs {
+    pub(crate) branding: Arc<Branding>,
+    pub(crate) fragment: bool,
+    #[serde(skip)]
+    pub(crate) overrides: Arc<TemplateOverrides>,
+    pub(crate) theme: Theme,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FragmentQuery {
+    #[serde(default, deserialize_with = "deserialize_flag")]
+    fragment: bool,
+}
+
+pub(super) fn find_cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(n, _)| *n == name)
+        .map(|(_, value)| value)
+}
+
+impl Prefs {
+    fn from_headers(
+        headers: &HeaderMap,
+        branding: Arc<Branding>,
+        overrides: Arc<TemplateOverrides>,
+    ) -> Self {
+        let theme = find_cookie(headers, THEME_COOKIE)
+            .and_then(|value| match value {
+                "dark" => Some(Theme::Dark),
+                "light" => Some(Theme::Light),
+                _ => None,
+            })
+            .unwrap_or_default();
+        Prefs {
+            branding,
+            fragment: false,
+            overrides,
+            theme,
+        }
+    }
+
+    pub(super) fn from_request(headers: &HeaderMap, uri: &Uri, state: &AppState) -> Self {
+        let fragment = Query::<FragmentQuery>::try_from_uri(uri)
+            .map(|q| q.0.fragment)
+            .unwrap_or_default();
+        Prefs {
+            fragment,
+            ..Prefs::from_headers(headers, state.branding.clone(), state.overrides.clone())
+        }
+    }
+}
+
+impl FromRequestParts<AppState> for Prefs {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        Ok(Prefs::from_request(&parts.headers, &parts.uri, state))
+    }
+}
+
+pub(crate) fn router() -> Router {
+    Router::new().route("/prefs", post(update_prefs))
+}
+
+#[derive(Debug, Deserialize)]
+struct PrefsForm {
+    theme: Theme,
+}
+
+async fn update_prefs(
+    headers: HeaderMap,
+    form: Result<Form<PrefsForm>, FormRejection>,
+) -> RenderResult<Response> {
+    let theme = form?.0.theme;
+    let cookie = match theme.name() {
+        Some(name) => format!(
+            "{THEME_COOKIE}={name}; Path=/; Max-Age={PREFS_COOKIE_MAX_AGE}; SameSite=Lax; HttpOnly"
+        ),
+        None => format!("{THEME_COOKIE}=; Path=/; Max-Age=0; SameSite=Lax; HttpOnly"),
+    };
+    let mut response = Redirect::to(&referer_path(&headers)).into_response();
+    if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+        response.headers_mut().insert(header::SET_COOKIE, cookie);
+    }
+    Ok(response)
+}
+
+/// The path of the `Referer` to send the browser back to, or `/git`. Only
+/// paths on this host are kept: one starting with `//` or `/\` would be
+/// taken by the browser as another host.
+fn referer_path(headers: &HeaderMap) -> String {
+    headers
+        .get(header::REFERER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<Uri>().ok())
+        .and_then(|uri| uri.path_and_query().map(ToString::to_string))
+        .filter(|path| {
+            path.starts_with('/') && !path[1..].starts_with('/') && !path[1..].starts_with('\\')
+        })
+        .unwrap_or_else(|| String::from("/git"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefs_from_headers() {
+        let sample = [
+            (vec![], Theme::System),
+            (vec!["wit_theme=dark"], Theme::Dark),
+            (vec!["a=b; wit_theme=light; c=d"], Theme::Light),
+            (vec!["a=b", "wit_theme=dark"], Theme::Dark),
+            (vec!["wit_theme=blue"], Theme::System),
+        ];
+        for (cookies, expected) in sample.into_iter() {
+            let mut headers = HeaderMap::new();
+            for cookie in cookies.iter() {
+                headers.append(header::COOKIE, HeaderValue::from_static(cookie));
+            }
+            assert_eq!(
+                Prefs::from_headers(&headers, Arc::default(), Arc::default()).theme,
+                expected,
+                "{cookies:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_referer_path() {
+        let sample = [
+            (None, "/git"),
+            (
+                Some("http://wit.local/git/tree?path=src"),
+                "/git/tree?path=src",
+            ),
+            (Some("/git/commits"), "/git/commits"),
+            (Some("http://wit.local//evil.example/x"), "/git"),
+            (Some("//evil.example/x"), "/git"),
+            (Some("http://wit.local/%5Cevil.example"), "/%5Cevil.example"),
+            (Some("not a uri"), "/git"),
+        ];
+        for (referer, expected) in sample.into_iter() {
+            let mut headers = HeaderMap::new();
+            if let Some(referer) = referer {
+                headers.insert(header::REFERER, HeaderValue::from_static(referer));
+            }
+            assert_eq!(referer_path(&headers), expected, "{referer:?}");
+        }
+    }
+}
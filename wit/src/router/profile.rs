@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{rejection::QueryRejection, Query},
+    http::header,
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use pprof::{protos::Message, ProfilerGuardBuilder};
+use serde::Deserialize;
+
+use super::{
+    api::{ApiError, ApiResult},
+    auth::require_write_token,
+    AppState,
+};
+
+const DEFAULT_PROFILE_SECONDS: u64 = 30;
+const MAX_PROFILE_SECONDS: u64 = 300;
+const SAMPLE_FREQUENCY: i32 = 99;
+
+/// Mounted outside the request timeout, since a profile takes as long as it
+/// is asked to.
+pub(crate) fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/debug/pprof/profile", get(profile))
+        .layer(middleware::from_fn_with_state(state, require_write_token))
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ProfileFormat {
+    Flamegraph,
+    #[default]
+    Protobuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfileQuery {
+    #[serde(default)]
+    format: ProfileFormat,
+    seconds: Option<u64>,
+}
+
+/// Samples the CPU for `seconds` and returns the profile as protobuf for
+/// `go tool pprof`, or as an SVG flamegraph. Only one profile can be taken
+/// at a time.
+async fn profile(query: Result<Query<ProfileQuery>, QueryRejection>) -> ApiResult<Response> {
+    let query = query?.0;
+    let seconds = query
+        .seconds
+        .unwrap_or(DEFAULT_PROFILE_SECONDS)
+        .clamp(1, MAX_PROFILE_SECONDS);
+    let guard = ProfilerGuardBuilder::default()
+        .frequency(SAMPLE_FREQUENCY)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()?;
+    tokio::time::sleep(Duration::from_secs(seconds)).await;
+    let report = guard.report().build()?;
+    drop(guard);
+    let mut body = vec![];
+    let content_type = match query.format {
+        ProfileFormat::Flamegraph => {
+            report.flamegraph(&mut body)?;
+            "image/svg+xml"
+        }
+        ProfileFormat::Protobuf => {
+            report
+                .pprof()?
+                .encode(&mut body)
+                .map_err(|e| ApiError::Profile(pprof::Error::IoError(e.into())))?;
+            "application/octet-stream"
+        }
+    };
+    Ok(([(header::CONTENT_TYPE, content_type)], body).into_response())
+}
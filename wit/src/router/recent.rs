@@ -0,0 +1,118 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, HeaderValue},
+};
+
+use super::prefs::find_cookie;
+
+const MAX_RECENT: usize = 8;
+const RECENT_COOKIE: &str = "wit_recent";
+const RECENT_COOKIE_MAX_AGE: u64 = 30 * 24 * 60 * 60;
+const SEPARATOR: char = ':';
+
+/// Files the visitor opened lately, most recent first, kept in a cookie
+/// since there are no server-side sessions.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct RecentViews(pub(crate) Vec<String>);
+
+fn encode(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    for b in path.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-._~".contains(&b) {
+            out.push(char::from(b));
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+fn decode(s: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+impl RecentViews {
+    fn parse(value: &str) -> Self {
+        RecentViews(
+            value
+                .split(SEPARATOR)
+                .filter_map(decode)
+                .filter(|p| !p.is_empty())
+                .take(MAX_RECENT)
+                .collect(),
+        )
+    }
+
+    /// Moves `path` to the front, dropping the oldest entry when full.
+    pub(crate) fn visit(mut self, path: &str) -> Self {
+        self.0.retain(|p| p != path);
+        self.0.insert(0, path.to_string());
+        self.0.truncate(MAX_RECENT);
+        self
+    }
+
+    pub(crate) fn cookie(&self) -> Option<HeaderValue> {
+        let value = self
+            .0
+            .iter()
+            .map(|p| encode(p))
+            .collect::<Vec<_>>()
+            .join(&SEPARATOR.to_string());
+        HeaderValue::from_str(&format!(
+            "{RECENT_COOKIE}={value}; Path=/; Max-Age={RECENT_COOKIE_MAX_AGE}; SameSite=Lax; HttpOnly"
+        ))
+        .ok()
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for RecentViews {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(find_cookie(&parts.headers, RECENT_COOKIE)
+            .map(RecentViews::parse)
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent_views() {
+        let recent = RecentViews::default()
+            .visit("src/main.rs")
+            .visit("docs/a b;c.md")
+            .visit("src/main.rs");
+        assert_eq!(recent.0, ["src/main.rs", "docs/a b;c.md"]);
+
+        let cookie = recent
+            .cookie()
+            .unwrap_or_else(|| panic!("recent views should make a valid cookie"));
+        let value = cookie
+            .to_str()
+            .ok()
+            .and_then(|c| c.split(';').next())
+            .and_then(|c| c.strip_prefix("wit_recent="))
+            .unwrap_or_default();
+        assert_eq!(value, "src/main.rs:docs/a%20b%3Bc.md");
+        assert_eq!(RecentViews::parse(value), recent);
+
+        let full = (0..20).fold(RecentViews::default(), |r, i| r.visit(&i.to_string()));
+        assert_eq!(full.0.len(), MAX_RECENT);
+        assert_eq!(full.0[0], "19");
+    }
+}
@@ -0,0 +1,51 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+
+pub(crate) use crate::service::task::current_request_id;
+use crate::service::task::REQUEST_ID;
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+pub(crate) async fn scope_request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(ToString::to_string);
+    match id {
+        Some(id) => REQUEST_ID.scope(id, next.run(request)).await,
+        None => next.run(request).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{body::Body, middleware::from_fn, routing::get, Router};
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_scope_request_id() {
+        assert_eq!(current_request_id(), None);
+
+        let app = Router::new()
+            .route(
+                "/",
+                get(|| async { current_request_id().unwrap_or_default() }),
+            )
+            .layer(from_fn(scope_request_id));
+        let request = Request::builder()
+            .uri("/")
+            .header(REQUEST_ID_HEADER, "42")
+            .body(Body::empty())
+            .unwrap_or_else(|e| panic!("build request failed: {e:?}"));
+        let response = app
+            .oneshot(request)
+            .await
+            .unwrap_or_else(|e| panic!("route request failed: {e:?}"));
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap_or_else(|e| panic!("read body failed: {e:?}"));
+        assert_eq!(&body[..], b"42");
+    }
+}
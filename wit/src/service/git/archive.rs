@@ -0,0 +1,213 @@
+use std::io::Write;
+
+use flate2::{write::GzEncoder, Compression};
+use git2::{ObjectType, Tree, TreeWalkMode, TreeWalkResult};
+
+use super::{GitError, GitRepository, GitResult};
+
+const BLOCK_SIZE: usize = 512;
+
+const FILEMODE_EXECUTABLE: i32 = 0o100755;
+const FILEMODE_LINK: i32 = 0o120000;
+
+struct Header {
+    link: Vec<u8>,
+    mode: u32,
+    mtime: i64,
+    path: Vec<u8>,
+    size: usize,
+    typeflag: u8,
+}
+
+fn write_octal(field: &mut [u8], value: u64) {
+    let width = field.len() - 1;
+    let digits = format!("{value:0width$o}");
+    field[..width].copy_from_slice(&digits.as_bytes()[digits.len() - width..]);
+    field[width] = 0;
+}
+
+fn write_block<W: Write>(writer: &mut W, data: &[u8]) -> std::io::Result<()> {
+    writer.write_all(data)?;
+    let padding = (BLOCK_SIZE - data.len() % BLOCK_SIZE) % BLOCK_SIZE;
+    writer.write_all(&[0; BLOCK_SIZE][..padding])
+}
+
+fn write_header<W: Write>(writer: &mut W, header: &Header) -> std::io::Result<()> {
+    if header.path.len() > 100 || header.link.len() > 100 {
+        let (typeflag, name) = if header.path.len() > 100 {
+            (b'L', &header.path)
+        } else {
+            (b'K', &header.link)
+        };
+        let mut long_name = name.clone();
+        long_name.push(0);
+        write_header(
+            writer,
+            &Header {
+                link: vec![],
+                mode: 0,
+                mtime: 0,
+                path: b"././@LongLink".to_vec(),
+                size: long_name.len(),
+                typeflag,
+            },
+        )?;
+        write_block(writer, &long_name)?;
+    }
+
+    let mut block = [0u8; BLOCK_SIZE];
+    let path = &header.path[..header.path.len().min(100)];
+    block[..path.len()].copy_from_slice(path);
+    write_octal(&mut block[100..108], header.mode.into());
+    write_octal(&mut block[108..116], 0);
+    write_octal(&mut block[116..124], 0);
+    write_octal(&mut block[124..136], header.size as u64);
+    write_octal(&mut block[136..148], header.mtime.max(0) as u64);
+    block[156] = header.typeflag;
+    let link = &header.link[..header.link.len().min(100)];
+    block[157..157 + link.len()].copy_from_slice(link);
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+
+    block[148..156].fill(b' ');
+    let checksum: u32 = block.iter().map(|&b| u32::from(b)).sum();
+    write_octal(&mut block[148..155], checksum.into());
+    block[155] = b' ';
+    writer.write_all(&block)
+}
+
+/// The archive is built in memory, so the compressed output so far and the
+/// blob about to be added are checked against the memory budget.
+pub(super) fn write_archive(
+    repo: &GitRepository,
+    tree: &Tree<'_>,
+    prefix: &str,
+    mtime: i64,
+) -> GitResult<Vec<u8>> {
+    let odb = repo.repo.odb()?;
+    let mut writer = GzEncoder::new(vec![], Compression::default());
+    let mut result = Ok(());
+    let walked = tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+        if repo.deadline.is_expired() {
+            result = Err(GitError::Timeout);
+            return TreeWalkResult::Abort;
+        }
+        let path = format!(
+            "{prefix}{root}{}",
+            String::from_utf8_lossy(entry.name_bytes())
+        );
+        let written = match entry.kind() {
+            Some(ObjectType::Tree) => write_header(
+                &mut writer,
+                &Header {
+                    link: vec![],
+                    mode: 0o755,
+                    mtime,
+                    path: format!("{path}/").into_bytes(),
+                    size: 0,
+                    typeflag: b'5',
+                },
+            )
+            .map_err(GitError::from),
+            Some(ObjectType::Blob) => odb
+                .read_header(entry.id())
+                .map_err(GitError::from)
+                .and_then(|(size, _)| {
+                    repo.check_budget(
+                        "archive",
+                        writer.get_ref().len() + size,
+                        "download a subdirectory or clone the repository instead",
+                    )?;
+                    Ok(repo.repo.find_blob(entry.id())?)
+                })
+                .and_then(|blob| {
+                    let header = match entry.filemode() {
+                        FILEMODE_LINK => Header {
+                            link: blob.content().to_vec(),
+                            mode: 0o777,
+                            mtime,
+                            path: path.into_bytes(),
+                            size: 0,
+                            typeflag: b'2',
+                        },
+                        mode => Header {
+                            link: vec![],
+                            mode: if mode == FILEMODE_EXECUTABLE {
+                                0o755
+                            } else {
+                                0o644
+                            },
+                            mtime,
+                            path: path.into_bytes(),
+                            size: blob.size(),
+                            typeflag: b'0',
+                        },
+                    };
+                    write_header(&mut writer, &header)?;
+                    if header.typeflag == b'0' {
+                        write_block(&mut writer, blob.content())?;
+                    }
+                    Ok(())
+                }),
+            _ => Ok(()),
+        };
+        match written {
+            Ok(()) => TreeWalkResult::Ok,
+            Err(e) => {
+                result = Err(e);
+                TreeWalkResult::Abort
+            }
+        }
+    });
+    result?;
+    walked?;
+    writer.write_all(&[0; BLOCK_SIZE * 2])?;
+    Ok(writer.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_header() {
+        let mut block = vec![];
+        let header = Header {
+            link: vec![],
+            mode: 0o644,
+            mtime: 0,
+            path: b"README.md".to_vec(),
+            size: 4,
+            typeflag: b'0',
+        };
+        write_header(&mut block, &header)
+            .unwrap_or_else(|e| panic!("write tar header failed: {e:?}"));
+        assert_eq!(block.len(), BLOCK_SIZE);
+        assert_eq!(&block[..9], b"README.md");
+        assert_eq!(&block[124..136], b"00000000004\0");
+        assert_eq!(&block[257..263], b"ustar\0");
+
+        let checksum = block
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u32::from(b)
+                }
+            })
+            .sum::<u32>();
+        assert_eq!(&block[148..156], format!("{checksum:06o}\0 ").as_bytes());
+
+        let mut blocks = vec![];
+        let header = Header {
+            path: "a/".repeat(60).into_bytes(),
+            ..header
+        };
+        write_header(&mut blocks, &header)
+            .unwrap_or_else(|e| panic!("write tar header failed: {e:?}"));
+        assert_eq!(blocks.len(), BLOCK_SIZE * 3);
+        assert_eq!(blocks[156], b'L');
+    }
+}
@@ -0,0 +1,276 @@
+use std::path::Path;
+
+use git2::{
+    AttrCheckFlags, AttrValue, ErrorCode, FileMode, Index, IndexEntry, IndexTime, ObjectType, Oid,
+    Repository, Tree, TreeWalkMode, TreeWalkResult,
+};
+
+use super::GitResult;
+
+const GITATTRIBUTES: &str = ".gitattributes";
+
+pub(super) struct LinguistAttributes {
+    pub(super) excluded: bool,
+    pub(super) language: Option<String>,
+}
+
+fn get_attr<'r>(
+    repo: &'r Repository,
+    flags: AttrCheckFlags,
+    path: &str,
+    name: &str,
+) -> AttrValue<'r> {
+    repo.get_attr_bytes(Path::new(path), name, flags)
+        .map(AttrValue::from_bytes)
+        .unwrap_or(AttrValue::Unspecified)
+}
+
+/// Attributes from the `.gitattributes` files of a tree, for paths in that
+/// tree, rather than from the working tree and index.
+pub(super) struct TreeAttributes {
+    /// The `.gitattributes` files by path and blob id.
+    files: Vec<(String, Oid)>,
+    /// A second handle on the repository whose index holds only `files`, so
+    /// that libgit2 reads attributes from them without touching the real
+    /// index.
+    repo: Repository,
+}
+
+impl TreeAttributes {
+    /// With every `.gitattributes` of `tree`, for looking up paths all over
+    /// it.
+    pub(super) fn new(repo: &Repository, tree: &Tree<'_>) -> GitResult<Self> {
+        let mut files = vec![];
+        tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+            if entry.name_bytes() == GITATTRIBUTES.as_bytes()
+                && entry.kind() == Some(ObjectType::Blob)
+            {
+                files.push((format!("{root}{GITATTRIBUTES}"), entry.id()));
+            }
+            TreeWalkResult::Ok
+        })?;
+        Self::with_files(repo, files)
+    }
+
+    /// With only the `.gitattributes` in the directories leading to `path`.
+    pub(super) fn for_path(repo: &Repository, tree: &Tree<'_>, path: &str) -> GitResult<Self> {
+        let mut files = vec![];
+        let mut dir = String::new();
+        for component in path.trim_matches('/').split('/') {
+            let file = format!("{dir}{GITATTRIBUTES}");
+            match tree.get_path(Path::new(&file)) {
+                Ok(entry) if entry.kind() == Some(ObjectType::Blob) => {
+                    files.push((file, entry.id()))
+                }
+                Ok(_) => {}
+                Err(e) if e.code() == ErrorCode::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+            dir.push_str(component);
+            dir.push('/');
+        }
+        Self::with_files(repo, files)
+    }
+
+    /// Another handle with the same files, for use on another thread.
+    pub(super) fn try_clone(&self) -> GitResult<Self> {
+        Self::with_files(&self.repo, self.files.clone())
+    }
+
+    fn with_files(repo: &Repository, files: Vec<(String, Oid)>) -> GitResult<Self> {
+        let attributes = Repository::open(repo.path())?;
+        let mut index = Index::new()?;
+        for (path, id) in &files {
+            index.add(&IndexEntry {
+                ctime: IndexTime::new(0, 0),
+                mtime: IndexTime::new(0, 0),
+                dev: 0,
+                ino: 0,
+                mode: u32::from(FileMode::Blob),
+                uid: 0,
+                gid: 0,
+                file_size: 0,
+                id: *id,
+                flags: 0,
+                flags_extended: 0,
+                path: path.as_bytes().to_vec(),
+            })?;
+        }
+        attributes.set_index(&mut index)?;
+        Ok(TreeAttributes {
+            files,
+            repo: attributes,
+        })
+    }
+
+    pub(super) fn binary(&self, path: &str) -> Option<bool> {
+        binary_attribute_from(&self.repo, AttrCheckFlags::INDEX_ONLY, path)
+    }
+
+    pub(super) fn linguist(&self, path: &str) -> LinguistAttributes {
+        linguist_attributes_from(&self.repo, AttrCheckFlags::INDEX_ONLY, path)
+    }
+}
+
+/// From the working tree, then the index.
+pub(super) fn binary_attribute(repo: &Repository, path: &str) -> Option<bool> {
+    binary_attribute_from(repo, AttrCheckFlags::FILE_THEN_INDEX, path)
+}
+
+fn binary_attribute_from(repo: &Repository, flags: AttrCheckFlags, path: &str) -> Option<bool> {
+    let get = |name| get_attr(repo, flags, path, name);
+    match (get("binary"), get("text"), get("diff")) {
+        (AttrValue::True, _, _) | (_, AttrValue::False, _) | (_, _, AttrValue::False) => Some(true),
+        (_, AttrValue::True, _)
+        | (_, _, AttrValue::True | AttrValue::String(_) | AttrValue::Bytes(_)) => Some(false),
+        _ => None,
+    }
+}
+
+fn linguist_attributes_from(
+    repo: &Repository,
+    flags: AttrCheckFlags,
+    path: &str,
+) -> LinguistAttributes {
+    let get = |name| get_attr(repo, flags, path, name);
+    let excluded = [
+        "linguist-documentation",
+        "linguist-generated",
+        "linguist-vendored",
+    ]
+    .into_iter()
+    .any(|name| matches!(get(name), AttrValue::True));
+    let language = match get("linguist-language") {
+        AttrValue::String(language) => Some(language.to_string()),
+        _ => None,
+    };
+    LinguistAttributes { excluded, language }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::write;
+
+    use git2::{FileMode, Oid};
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_attributes() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo =
+            Repository::init(path).unwrap_or_else(|e| panic!("initialize git repo failed: {e:?}"));
+        write(
+            path.join(".gitattributes"),
+            "*.dat binary\n*.lock -text\n*.svg -diff\n*.bin text\n*.md diff=markdown\n",
+        )
+        .unwrap_or_else(|e| panic!("write .gitattributes failed: {e:?}"));
+
+        let sample = [
+            ("data/blob.dat", Some(true)),
+            ("Cargo.lock", Some(true)),
+            ("logo.svg", Some(true)),
+            ("firmware.bin", Some(false)),
+            ("README.md", Some(false)),
+            ("main.rs", None),
+        ];
+        for (file, binary) in sample.into_iter() {
+            assert_eq!(
+                binary_attribute(&repo, file),
+                binary,
+                "unexpected binary attribute of {file}"
+            );
+        }
+    }
+
+    fn write_tree(repo: &Repository, entries: &[(&str, Oid, FileMode)]) -> Oid {
+        let mut builder = repo
+            .treebuilder(None)
+            .unwrap_or_else(|e| panic!("create tree builder failed: {e:?}"));
+        for (name, id, mode) in entries {
+            builder
+                .insert(name, *id, (*mode).into())
+                .unwrap_or_else(|e| panic!("insert {name} failed: {e:?}"));
+        }
+        builder
+            .write()
+            .unwrap_or_else(|e| panic!("write tree failed: {e:?}"))
+    }
+
+    #[test]
+    fn test_tree_attributes() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let repo = Repository::init_bare(dir.path())
+            .unwrap_or_else(|e| panic!("initialize bare git repo failed: {e:?}"));
+        let blob = |content: &str| {
+            repo.blob(content.as_bytes())
+                .unwrap_or_else(|e| panic!("write blob failed: {e:?}"))
+        };
+        let docs = write_tree(
+            &repo,
+            &[(
+                ".gitattributes",
+                blob("*.md linguist-documentation\n"),
+                FileMode::Blob,
+            )],
+        );
+        let root = write_tree(
+            &repo,
+            &[
+                (
+                    ".gitattributes",
+                    blob(concat!(
+                        "*.dat binary\nvendor/** linguist-vendored\n",
+                        "*.pb.go linguist-generated\n*.h linguist-language=C++\n",
+                    )),
+                    FileMode::Blob,
+                ),
+                ("docs", docs, FileMode::Tree),
+            ],
+        );
+        let tree = repo
+            .find_tree(root)
+            .unwrap_or_else(|e| panic!("find tree failed: {e:?}"));
+        let attributes = TreeAttributes::new(&repo, &tree)
+            .unwrap_or_else(|e| panic!("read tree attributes failed: {e:?}"));
+
+        assert_eq!(attributes.binary("data/blob.dat"), Some(true));
+        assert_eq!(attributes.binary("main.rs"), None);
+        let sample = [
+            ("vendor/lib/lib.js", true, None),
+            ("api/service.pb.go", true, None),
+            ("include/wit.h", false, Some("C++")),
+            ("docs/guide.md", true, None),
+            ("README.md", false, None),
+            ("main.rs", false, None),
+        ];
+        for (file, excluded, language) in sample.into_iter() {
+            for attributes in [
+                attributes.linguist(file),
+                TreeAttributes::for_path(&repo, &tree, file)
+                    .unwrap_or_else(|e| panic!("read attributes of {file} failed: {e:?}"))
+                    .linguist(file),
+            ] {
+                assert_eq!(
+                    attributes.excluded, excluded,
+                    "unexpected exclusion of {file}"
+                );
+                assert_eq!(
+                    attributes.language.as_deref(),
+                    language,
+                    "unexpected language of {file}"
+                );
+            }
+        }
+
+        let attributes = TreeAttributes::for_path(&repo, &tree, "include/wit.h")
+            .unwrap_or_else(|e| panic!("read attributes of include/wit.h failed: {e:?}"));
+        assert_eq!(attributes.files.len(), 1);
+        let attributes = attributes
+            .try_clone()
+            .unwrap_or_else(|e| panic!("clone tree attributes failed: {e:?}"));
+        assert!(!attributes.linguist("docs/guide.md").excluded);
+    }
+}
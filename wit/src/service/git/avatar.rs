@@ -0,0 +1,30 @@
+use sha2::{Digest, Sha256};
+
+pub(super) fn avatar_url(template: &str, email: &str) -> String {
+    let digest = Sha256::digest(email.trim().to_lowercase().as_bytes());
+    let hash = digest
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    template.replace("{hash}", &hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_avatar_url() {
+        let hash = "84059b07d4be67b806386c0aad8070a23f18836bbaae342275dc0a83414c32ee";
+        let sample = [
+            "https://www.gravatar.com/avatar/{hash}?d=identicon",
+            "https://seccdn.libravatar.org/avatar/{hash}",
+        ];
+        for template in sample.into_iter() {
+            assert_eq!(
+                avatar_url(template, " MyEmailAddress@example.com "),
+                template.replace("{hash}", hash)
+            );
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use serde::Deserialize;
+
+use super::{
+    model::{GitBlob, GitCommit, GitObjectType, GitOid},
+    GitError, GitRepository, GitResult, MAX_COMMIT_COUNT,
+};
+
+pub(crate) const MAX_BATCH_ITEMS: usize = 100;
+
+/// A blob named either by id or by `ref` and `path`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum BlobSpec {
+    Id {
+        id: GitOid,
+    },
+    Path {
+        #[serde(rename = "ref")]
+        reference: String,
+        path: String,
+    },
+}
+
+fn check_batch_size(len: usize, max: usize) -> GitResult<()> {
+    if len > max {
+        return Err(GitError::TooLarge(format!(
+            "{len} items is over the limit of {max} per batch"
+        )));
+    }
+    Ok(())
+}
+
+impl GitRepository {
+    /// Results are in the order of `specs` and fail individually. Once the
+    /// blobs read together exceed the memory budget, the rest fail as too
+    /// large.
+    pub(crate) fn get_blob_batch(
+        &self,
+        specs: Vec<BlobSpec>,
+        max_size: usize,
+    ) -> GitResult<Vec<GitResult<GitBlob>>> {
+        check_batch_size(specs.len(), MAX_BATCH_ITEMS)?;
+        let mut total = 0;
+        let mut results = Vec::with_capacity(specs.len());
+        for spec in specs {
+            self.deadline.check()?;
+            let result = self
+                .check_budget(
+                    format_args!("batch of blobs ({total} bytes)"),
+                    total,
+                    "request fewer blobs per batch",
+                )
+                .and_then(|_| match spec {
+                    BlobSpec::Id { id } => {
+                        self.check_object(&id, Some(GitObjectType::Blob))?;
+                        self.get_blob(id, None, None, max_size)
+                    }
+                    BlobSpec::Path { reference, path } => {
+                        let id = self.resolve_tree(&format!("{reference}:{path}"))?;
+                        self.check_object(&id, Some(GitObjectType::Blob))?;
+                        let root = self.resolve_tree(&reference)?;
+                        self.get_blob(id, Some(&path), Some(&root), max_size)
+                    }
+                });
+            if let Ok(blob) = &result {
+                total += blob.size;
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Commit metadata without stats, in the order of `ids`.
+    pub(crate) fn get_commit_batch(
+        &self,
+        ids: Vec<GitOid>,
+        mailmap: bool,
+    ) -> GitResult<Vec<GitResult<GitCommit>>> {
+        check_batch_size(ids.len(), MAX_COMMIT_COUNT)?;
+        let mailmap = self.mailmap(mailmap);
+        let mut results = Vec::with_capacity(ids.len());
+        for id in ids {
+            self.deadline.check()?;
+            results.push(
+                self.repo
+                    .find_commit(id.0)
+                    .map(|c| self.convert_to_commit(&c, mailmap.as_ref()))
+                    .map_err(Into::into),
+            );
+        }
+        Ok(results)
+    }
+}
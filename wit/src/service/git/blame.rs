@@ -0,0 +1,116 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use git2::Oid;
+use lru::LruCache;
+
+use super::{
+    model::{GitBlameHunk, GitOid},
+    GitRepository, GitResult,
+};
+
+const BLAME_CACHE_SIZE: usize = 256;
+
+type BlameEntries = LruCache<(Oid, Oid, bool), Vec<GitBlameHunk>>;
+
+#[derive(Clone, Debug)]
+pub(crate) struct BlameCache {
+    entries: Arc<Mutex<BlameEntries>>,
+}
+
+impl Default for BlameCache {
+    fn default() -> Self {
+        let capacity = NonZeroUsize::new(BLAME_CACHE_SIZE).unwrap_or(NonZeroUsize::MIN);
+        BlameCache {
+            entries: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+}
+
+impl BlameCache {
+    fn lock(&self) -> MutexGuard<'_, BlameEntries> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Returns the number of cached entries and the capacity.
+    pub(crate) fn usage(&self) -> (usize, usize) {
+        let entries = self.lock();
+        (entries.len(), entries.cap().get())
+    }
+
+    pub(crate) fn blame_file(
+        &self,
+        repo: &GitRepository,
+        id: &GitOid,
+        path: &str,
+        mailmap: bool,
+    ) -> GitResult<Vec<GitBlameHunk>> {
+        let key = (repo.get_path_id(id, path)?.0, id.0, mailmap);
+        if let Some(hunks) = self.lock().get(&key) {
+            return Ok(hunks.clone());
+        }
+        let hunks = repo.blame_file(id, path, mailmap)?;
+        self.lock().put(key, hunks.clone());
+        Ok(hunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::File, io::Write, path::Path};
+
+    use git2::{Repository, Signature};
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_blame_file() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo =
+            Repository::init(path).unwrap_or_else(|e| panic!("initialize git repo failed: {e:?}"));
+        let mut file = File::create(path.join("README.md"))
+            .unwrap_or_else(|e| panic!("create file failed: {e:?}"));
+        writeln!(file, "wit").unwrap_or_else(|e| panic!("write file failed: {e:?}"));
+        let mut index = repo
+            .index()
+            .unwrap_or_else(|e| panic!("get git index failed: {e:?}"));
+        index
+            .add_path(Path::new("README.md"))
+            .unwrap_or_else(|e| panic!("add file to git index failed: {e:?}"));
+        let tree = index
+            .write_tree()
+            .and_then(|id| repo.find_tree(id))
+            .unwrap_or_else(|e| panic!("write git index failed: {e:?}"));
+        let sig = Signature::now("wit", "wit@example.com")
+            .unwrap_or_else(|e| panic!("create git signature failed: {e:?}"));
+        let commit_id = repo
+            .commit(Some("HEAD"), &sig, &sig, "Initial commit", &tree, &[])
+            .unwrap_or_else(|e| panic!("create git commit failed: {e:?}"));
+        drop(tree);
+
+        let repo: GitRepository = repo.into();
+        let cache = BlameCache::default();
+        for _ in 0..2 {
+            let hunks = cache
+                .blame_file(&repo, &commit_id.into(), "README.md", true)
+                .unwrap_or_else(|e| {
+                    panic!("blame_file in git repo {path:?} should not fail: {e:?}")
+                });
+            assert_eq!(hunks.len(), 1);
+            assert_eq!(hunks[0].commit_id.0, commit_id);
+        }
+        assert_eq!(cache.lock().len(), 1);
+
+        cache
+            .blame_file(&repo, &commit_id.into(), "LICENSE", true)
+            .expect_err("blame_file of a missing path should fail");
+    }
+}
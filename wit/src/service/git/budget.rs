@@ -0,0 +1,22 @@
+use super::{GitError, GitRepository, GitResult};
+
+pub(super) const DEFAULT_MEMORY_BUDGET: usize = 64 * 1024 * 1024;
+
+impl GitRepository {
+    /// Rejects loading `size` bytes of `what` into memory for a single
+    /// request once it exceeds the configured budget.
+    pub(super) fn check_budget(
+        &self,
+        what: impl std::fmt::Display,
+        size: usize,
+        hint: &str,
+    ) -> GitResult<()> {
+        let budget = self.settings.memory_budget;
+        if size > budget {
+            return Err(GitError::TooLarge(format!(
+                "{what} is over the {budget} byte memory budget; {hint}"
+            )));
+        }
+        Ok(())
+    }
+}
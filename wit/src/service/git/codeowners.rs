@@ -0,0 +1,109 @@
+use regex::Regex;
+
+pub(super) const CODEOWNERS_PATHS: &[&str] = &[
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+pub(super) struct CodeOwnersRule {
+    pub(super) line_number: usize,
+    pub(super) owners: Vec<String>,
+    pub(super) pattern: String,
+    regex: Regex,
+}
+
+impl CodeOwnersRule {
+    fn is_match(&self, path: &str) -> bool {
+        self.regex.is_match(path.trim_start_matches('/'))
+    }
+}
+
+pub(super) fn match_rules<'r>(rules: &'r [CodeOwnersRule], path: &str) -> Vec<&'r CodeOwnersRule> {
+    rules.iter().rev().filter(|r| r.is_match(path)).collect()
+}
+
+pub(super) fn parse_rules(content: &str) -> Vec<CodeOwnersRule> {
+    content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = line.split_once(" #").map_or(line, |(l, _)| l).trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let mut parts = line.split_whitespace();
+            let pattern = parts.next()?;
+            Some(CodeOwnersRule {
+                line_number: i + 1,
+                owners: parts.map(str::to_string).collect(),
+                pattern: pattern.to_string(),
+                regex: Regex::new(&pattern_to_regex(pattern)).ok()?,
+            })
+        })
+        .collect()
+}
+
+fn pattern_to_regex(pattern: &str) -> String {
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let dir_only = pattern.ends_with('/');
+    let pattern = pattern.trim_matches('/');
+    let mut regex = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push_str(if dir_only { "/.*$" } else { "(?:/.*)?$" });
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_rules() {
+        let rules = parse_rules(
+            "# Default owners\n\
+             *       @wit/core\n\
+             *.rs    @wit/rust # inline comment\n\
+             /docs/  @wit/docs\n\
+             assets/**/*.css @wit/design\n\
+             templates @wit/web wit@example.com\n",
+        );
+        assert_eq!(rules.len(), 5);
+
+        let sample = [
+            ("README.md", vec![2]),
+            ("wit/src/main.rs", vec![3, 2]),
+            ("docs/guide.md", vec![4, 2]),
+            ("wit/docs/guide.md", vec![2]),
+            ("assets/site.css", vec![5, 2]),
+            ("assets/theme/dark.css", vec![5, 2]),
+            ("wit/templates/base.html", vec![6, 2]),
+        ];
+        for (path, lines) in sample.into_iter() {
+            let matched = match_rules(&rules, path)
+                .iter()
+                .map(|r| r.line_number)
+                .collect::<Vec<_>>();
+            assert_eq!(matched, lines, "unexpected rules for {path}");
+        }
+        assert_eq!(rules[3].pattern, "assets/**/*.css");
+        assert_eq!(rules[4].owners, ["@wit/web", "wit@example.com"]);
+    }
+}
@@ -0,0 +1,69 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use super::{GitError, GitResult};
+
+/// libgit2 calls can't be interrupted, so long revwalks and tree walks check
+/// this between commits and entries and give up once it has passed or the
+/// request waiting for them has gone away.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Deadline {
+    at: Option<Instant>,
+    cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl Deadline {
+    /// The returned guard cancels the deadline when dropped, so it should be
+    /// held by the future awaiting the blocking task.
+    pub(crate) fn after(timeout: Duration) -> (Self, CancelOnDrop) {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let deadline = Deadline {
+            at: Some(Instant::now() + timeout),
+            cancelled: Some(cancelled.clone()),
+        };
+        (deadline, CancelOnDrop(cancelled))
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled
+            .as_ref()
+            .is_some_and(|c| c.load(Ordering::Relaxed))
+    }
+
+    pub(super) fn is_expired(&self) -> bool {
+        self.is_cancelled() || self.at.is_some_and(|at| Instant::now() >= at)
+    }
+
+    pub(crate) fn check(&self) -> GitResult<()> {
+        if self.is_cancelled() {
+            Err(GitError::Cancelled)
+        } else if self.is_expired() {
+            Err(GitError::Timeout)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+pub(crate) struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+impl Deadline {
+    pub(super) fn expired() -> Self {
+        Deadline {
+            at: Some(Instant::now()),
+            cancelled: None,
+        }
+    }
+}
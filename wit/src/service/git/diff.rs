@@ -0,0 +1,250 @@
+use git2::{Delta, Diff, DiffFile, DiffLineType, Patch};
+use serde::Deserialize;
+
+use super::{
+    model::{GitDiffFile, GitDiffHunk, GitDiffLine, GitDiffLineType, GitDiffSpan},
+    GitRepository, GitResult,
+};
+
+const MAX_WORD_DIFF_TOKENS: usize = 512;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum IgnoreWhitespace {
+    All,
+    Change,
+    Eol,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct DiffOptions {
+    pub(crate) ignore_whitespace: Option<IgnoreWhitespace>,
+    pub(crate) word_diff: bool,
+}
+
+impl From<&DiffOptions> for git2::DiffOptions {
+    fn from(options: &DiffOptions) -> Self {
+        let mut opts = git2::DiffOptions::new();
+        match options.ignore_whitespace {
+            Some(IgnoreWhitespace::All) => opts.ignore_whitespace(true),
+            Some(IgnoreWhitespace::Change) => opts.ignore_whitespace_change(true),
+            Some(IgnoreWhitespace::Eol) => opts.ignore_whitespace_eol(true),
+            None => &mut opts,
+        };
+        opts
+    }
+}
+
+fn tokenize(s: &str) -> Vec<(usize, usize)> {
+    fn class(c: char) -> u8 {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    }
+
+    let mut tokens: Vec<(usize, usize)> = vec![];
+    let mut last = None;
+    for (i, c) in s.char_indices() {
+        let current = class(c);
+        match tokens.last_mut() {
+            Some(token) if current != 2 && last == Some(current) => token.1 = i + c.len_utf8(),
+            _ => tokens.push((i, i + c.len_utf8())),
+        }
+        last = Some(current);
+    }
+    tokens
+}
+
+fn merge_spans(tokens: &[(usize, usize)], common: &[bool]) -> Vec<GitDiffSpan> {
+    let mut spans: Vec<GitDiffSpan> = vec![];
+    for (&(start, end), &common) in tokens.iter().zip(common) {
+        if common {
+            continue;
+        }
+        match spans.last_mut() {
+            Some(span) if span.end == start => span.end = end,
+            _ => spans.push(GitDiffSpan { end, start }),
+        }
+    }
+    spans
+}
+
+fn word_spans(old: &str, new: &str) -> Option<(Vec<GitDiffSpan>, Vec<GitDiffSpan>)> {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    if old_tokens.len() > MAX_WORD_DIFF_TOKENS || new_tokens.len() > MAX_WORD_DIFF_TOKENS {
+        return None;
+    }
+    let token = |s: &str, (start, end): (usize, usize)| s[start..end].to_string();
+    let old_words: Vec<_> = old_tokens.iter().map(|&t| token(old, t)).collect();
+    let new_words: Vec<_> = new_tokens.iter().map(|&t| token(new, t)).collect();
+
+    let (n, m) = (old_words.len(), new_words.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old_words[i] == new_words[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut old_common = vec![false; n];
+    let mut new_common = vec![false; m];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old_words[i] == new_words[j] {
+            old_common[i] = true;
+            new_common[j] = true;
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    Some((
+        merge_spans(&old_tokens, &old_common),
+        merge_spans(&new_tokens, &new_common),
+    ))
+}
+
+fn add_word_spans(lines: &mut [GitDiffLine]) {
+    let mut i = 0;
+    while i < lines.len() {
+        let deletions = lines[i..]
+            .iter()
+            .take_while(|l| l.origin == GitDiffLineType::Deletion)
+            .count();
+        let additions = lines[i + deletions..]
+            .iter()
+            .take_while(|l| l.origin == GitDiffLineType::Addition)
+            .count();
+        for k in 0..deletions.min(additions) {
+            let (old, new) = (i + k, i + deletions + k);
+            if let Some((old_spans, new_spans)) =
+                word_spans(&lines[old].content.0, &lines[new].content.0)
+            {
+                lines[old].spans = Some(old_spans);
+                lines[new].spans = Some(new_spans);
+            }
+        }
+        i += (deletions + additions).max(1);
+    }
+}
+
+fn convert_patch(patch: &Patch<'_>, word_diff: bool) -> GitResult<Vec<GitDiffHunk>> {
+    let mut hunks = Vec::with_capacity(patch.num_hunks());
+    for hunk_idx in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_idx)?;
+        let mut lines = Vec::with_capacity(line_count);
+        for line_idx in 0..line_count {
+            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+            let origin = match line.origin_value() {
+                DiffLineType::Addition => GitDiffLineType::Addition,
+                DiffLineType::Context => GitDiffLineType::Context,
+                DiffLineType::Deletion => GitDiffLineType::Deletion,
+                _ => continue,
+            };
+            lines.push(GitDiffLine {
+                content: line.content().into(),
+                new_lineno: line.new_lineno(),
+                old_lineno: line.old_lineno(),
+                origin,
+                spans: None,
+            });
+        }
+        if word_diff {
+            add_word_spans(&mut lines);
+        }
+        hunks.push(GitDiffHunk {
+            header: hunk.header().into(),
+            lines,
+            new_lines: hunk.new_lines(),
+            new_start: hunk.new_start(),
+            old_lines: hunk.old_lines(),
+            old_start: hunk.old_start(),
+        });
+    }
+    Ok(hunks)
+}
+
+/// Both sides of every file are counted against the memory budget before
+/// their patch is loaded.
+pub(super) fn convert_diff(
+    repo: &GitRepository,
+    diff: &Diff<'_>,
+    options: &DiffOptions,
+) -> GitResult<Vec<GitDiffFile>> {
+    let odb = repo.repo.odb()?;
+    let size = |file: DiffFile<'_>| match file.size() {
+        0 if !file.id().is_zero() => odb.read_header(file.id()).map_or(0, |(size, _)| size),
+        size => size as usize,
+    };
+    let mut files = Vec::with_capacity(diff.deltas().len());
+    let mut loaded = 0;
+    for (idx, delta) in diff.deltas().enumerate() {
+        loaded += size(delta.old_file()) + size(delta.new_file());
+        repo.check_budget(
+            "diff",
+            loaded,
+            "compare fewer commits or view the files separately",
+        )?;
+        let hunks = match Patch::from_diff(diff, idx)? {
+            Some(patch) => convert_patch(&patch, options.word_diff)?,
+            None => vec![],
+        };
+        let path = |file: git2::DiffFile<'_>| match delta.status() {
+            Delta::Added if file.id().is_zero() => None,
+            Delta::Deleted if file.id().is_zero() => None,
+            _ => file.path_bytes().map(Into::into),
+        };
+        files.push(GitDiffFile {
+            hunks,
+            is_binary: delta.flags().is_binary(),
+            new_path: path(delta.new_file()),
+            old_path: path(delta.old_file()),
+            status: delta.status().into(),
+        });
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let s = "let foo_bar = baz(1);";
+        let tokens: Vec<_> = tokenize(s).into_iter().map(|(a, b)| &s[a..b]).collect();
+        assert_eq!(
+            tokens,
+            ["let", " ", "foo_bar", " ", "=", " ", "baz", "(", "1", ")", ";"]
+        );
+    }
+
+    #[test]
+    fn test_word_spans() {
+        let (old, new) = word_spans("let x = foo(1);\n", "let y = foo(1, 2);\n")
+            .unwrap_or_else(|| panic!("word_spans should not give up on short lines"));
+        assert_eq!(old, [GitDiffSpan { end: 5, start: 4 }]);
+        assert_eq!(
+            new,
+            [
+                GitDiffSpan { end: 5, start: 4 },
+                GitDiffSpan { end: 16, start: 13 },
+            ]
+        );
+
+        let long = "x ".repeat(MAX_WORD_DIFF_TOKENS);
+        assert!(word_spans(&long, "x").is_none());
+    }
+}
@@ -0,0 +1,102 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+
+pub(super) fn count_lines(bytes: &[u8]) -> usize {
+    let newlines = bytes.iter().filter(|&&b| b == b'\n').count();
+    match bytes.last() {
+        Some(b'\n') | None => newlines,
+        Some(_) => newlines + 1,
+    }
+}
+
+pub(super) fn decode(bytes: &[u8]) -> (String, &'static str) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return (text.into_owned(), encoding.name());
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return (text.into(), UTF_8.name());
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    let (text, _) = encoding.decode_without_bom_handling(bytes);
+    (text.into_owned(), encoding.name())
+}
+
+pub(super) fn has_bom(bytes: &[u8]) -> bool {
+    Encoding::for_bom(bytes).is_some()
+}
+
+pub(super) fn has_crlf(bytes: &[u8]) -> bool {
+    bytes.windows(2).any(|w| w == b"\r\n")
+}
+
+pub(super) fn truncate(bytes: &[u8], max_size: usize) -> &[u8] {
+    if bytes.len() <= max_size {
+        return bytes;
+    }
+    let end = match std::str::from_utf8(&bytes[..max_size]) {
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        _ => max_size,
+    };
+    &bytes[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_lines() {
+        let sample: [(&[u8], usize); 5] = [
+            (b"", 0),
+            (b"wit", 1),
+            (b"wit\n", 1),
+            (b"git\r\nweb\r\nwit", 3),
+            (b"\n\n", 2),
+        ];
+        for (bytes, count) in sample.into_iter() {
+            assert_eq!(
+                count_lines(bytes),
+                count,
+                "unexpected line count of {bytes:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let sample: [(&[u8], &str, &str); 5] = [
+            (b"git + web = wit\n", "git + web = wit\n", "UTF-8"),
+            (b"\xEF\xBB\xBFwit", "wit", "UTF-8"),
+            (b"\xFF\xFEw\0i\0t\0", "wit", "UTF-16LE"),
+            (b"\xFE\xFF\0w\0i\0t", "wit", "UTF-16BE"),
+            (
+                b"caf\xE9 cr\xE8me br\xFBl\xE9e",
+                "caf\u{e9} cr\u{e8}me br\u{fb}l\u{e9}e",
+                "windows-1252",
+            ),
+        ];
+        for (bytes, text, encoding) in sample.into_iter() {
+            assert_eq!(
+                decode(bytes),
+                (text.into(), encoding),
+                "unexpected decoding of {bytes:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_truncate() {
+        let sample: [(&[u8], usize, &[u8]); 4] = [
+            (b"wit", 8, b"wit"),
+            (b"git + web = wit", 3, b"git"),
+            ("caf\u{e9}".as_bytes(), 4, b"caf"),
+            (b"\xFF\xFE\xFD", 2, b"\xFF\xFE"),
+        ];
+        for (bytes, max_size, truncated) in sample.into_iter() {
+            assert_eq!(truncate(bytes, max_size), truncated);
+        }
+    }
+}
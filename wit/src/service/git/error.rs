@@ -6,16 +6,32 @@ pub(crate) type GitResult<T> = Result<T, GitError>;
 
 #[derive(Debug)]
 pub(crate) enum GitError {
+    Cancelled,
+    Conflict(String),
+    InvalidArgument(String),
+    InvalidPatch(String),
+    InvalidReference(String),
     ObjectNotFound(String),
+    ReferenceNotFound(String),
     RepositoryNotFound(Box<Path>),
+    Timeout,
+    TooLarge(String),
     Unhandled(String),
 }
 
 impl std::fmt::Display for GitError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            GitError::Cancelled => write!(f, "Cancelled"),
+            GitError::Conflict(message) => write!(f, "Conflict: {message}"),
+            GitError::InvalidArgument(message) => write!(f, "InvalidArgument: {message}"),
+            GitError::InvalidPatch(message) => write!(f, "InvalidPatch: {message}"),
+            GitError::InvalidReference(message) => write!(f, "InvalidReference: {message}"),
             GitError::ObjectNotFound(message) => write!(f, "ObjectNotFound: {message}"),
+            GitError::ReferenceNotFound(message) => write!(f, "ReferenceNotFound: {message}"),
             GitError::RepositoryNotFound(path) => write!(f, "RepositoryNotFound: {:?}", path),
+            GitError::Timeout => write!(f, "Timeout"),
+            GitError::TooLarge(message) => write!(f, "TooLarge: {message}"),
             GitError::Unhandled(message) => write!(f, "{message}"),
         }
     }
@@ -27,6 +43,12 @@ impl From<git2::Error> for GitError {
     fn from(e: git2::Error) -> Self {
         match (e.class(), e.code()) {
             (ErrorClass::Odb, ErrorCode::NotFound) => GitError::ObjectNotFound(e.message().into()),
+            (ErrorClass::Tree, ErrorCode::NotFound) => GitError::ObjectNotFound(e.message().into()),
+            (ErrorClass::Reference, ErrorCode::NotFound) => {
+                GitError::ReferenceNotFound(e.message().into())
+            }
+            (_, ErrorCode::InvalidSpec) => GitError::InvalidReference(e.message().into()),
+            (ErrorClass::Net, ErrorCode::Timeout) => GitError::Timeout,
             _ => GitError::Unhandled(format!(
                 "Unhandled {:?} {:?}: {}",
                 e.class(),
@@ -36,3 +58,9 @@ impl From<git2::Error> for GitError {
         }
     }
 }
+
+impl From<std::io::Error> for GitError {
+    fn from(e: std::io::Error) -> Self {
+        GitError::Unhandled(format!("Unhandled IoError: {e}"))
+    }
+}
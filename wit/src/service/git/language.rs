@@ -0,0 +1,87 @@
+const LANGUAGE_BY_EXTENSION: &[(&str, &str)] = &[
+    ("bash", "Shell"),
+    ("c", "C"),
+    ("cc", "C++"),
+    ("cpp", "C++"),
+    ("cs", "C#"),
+    ("css", "CSS"),
+    ("go", "Go"),
+    ("h", "C"),
+    ("hpp", "C++"),
+    ("html", "HTML"),
+    ("java", "Java"),
+    ("js", "JavaScript"),
+    ("json", "JSON"),
+    ("jsx", "JavaScript"),
+    ("kt", "Kotlin"),
+    ("lua", "Lua"),
+    ("md", "Markdown"),
+    ("mjs", "JavaScript"),
+    ("php", "PHP"),
+    ("py", "Python"),
+    ("rb", "Ruby"),
+    ("rs", "Rust"),
+    ("scss", "SCSS"),
+    ("sh", "Shell"),
+    ("sql", "SQL"),
+    ("svelte", "Svelte"),
+    ("swift", "Swift"),
+    ("toml", "TOML"),
+    ("ts", "TypeScript"),
+    ("tsx", "TypeScript"),
+    ("vue", "Vue"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("zig", "Zig"),
+];
+
+const LANGUAGE_BY_FILENAME: &[(&str, &str)] = &[
+    ("CMakeLists.txt", "CMake"),
+    ("Dockerfile", "Dockerfile"),
+    ("GNUmakefile", "Makefile"),
+    ("Makefile", "Makefile"),
+];
+
+pub(super) fn detect_language(path: &str) -> Option<&'static str> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    if let Some((_, language)) = LANGUAGE_BY_FILENAME.iter().find(|(n, _)| name.eq(*n)) {
+        return Some(language);
+    }
+    let (stem, extension) = name.rsplit_once('.')?;
+    if stem.is_empty() {
+        return None;
+    }
+    LANGUAGE_BY_EXTENSION
+        .binary_search_by(|(e, _)| e.cmp(&extension.to_ascii_lowercase().as_str()))
+        .ok()
+        .map(|i| LANGUAGE_BY_EXTENSION[i].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        let sample = [
+            ("src/main.rs", Some("Rust")),
+            ("wit/Dockerfile", Some("Dockerfile")),
+            ("README.MD", Some("Markdown")),
+            (".gitignore", None),
+            ("LICENSE", None),
+            ("archive.tar.gz", None),
+        ];
+        for (path, language) in sample.into_iter() {
+            assert_eq!(
+                detect_language(path),
+                language,
+                "unexpected language of {path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_extension_table_is_sorted() {
+        assert!(LANGUAGE_BY_EXTENSION.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+}
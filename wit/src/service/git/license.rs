@@ -0,0 +1,234 @@
+const LICENSE_FILE_PREFIXES: &[&str] = &["copying", "licence", "license", "unlicense"];
+
+/// How many non-empty lines at the top of a file may hold a license title.
+const TITLE_LINES: usize = 3;
+
+/// Licenses with a title are recognised by it alone, since their texts name
+/// related licenses too: GPL-3.0 mentions both the AGPL and the LGPL. The
+/// rest are recognised by phrases anywhere in their text.
+enum Pattern {
+    Phrases(&'static [&'static str]),
+    Title(&'static str),
+}
+
+const LICENSE_PATTERNS: &[(&str, &str, Pattern)] = &[
+    (
+        "AGPL-3.0",
+        "GNU Affero General Public License v3.0",
+        Pattern::Title("gnu affero general public license version 3"),
+    ),
+    (
+        "Apache-2.0",
+        "Apache License 2.0",
+        Pattern::Title("apache license version 2.0"),
+    ),
+    (
+        "BSD-3-Clause",
+        "BSD 3-Clause \"New\" or \"Revised\" License",
+        Pattern::Phrases(&[
+            "redistribution and use in source and binary forms",
+            "neither the name of",
+        ]),
+    ),
+    (
+        "BSD-2-Clause",
+        "BSD 2-Clause \"Simplified\" License",
+        Pattern::Phrases(&["redistribution and use in source and binary forms"]),
+    ),
+    (
+        "BSL-1.0",
+        "Boost Software License 1.0",
+        Pattern::Title("boost software license version 1.0"),
+    ),
+    (
+        "CC0-1.0",
+        "Creative Commons Zero v1.0 Universal",
+        Pattern::Title("cc0 1.0 universal"),
+    ),
+    (
+        "LGPL-3.0",
+        "GNU Lesser General Public License v3.0",
+        Pattern::Title("gnu lesser general public license version 3"),
+    ),
+    (
+        "LGPL-2.1",
+        "GNU Lesser General Public License v2.1",
+        Pattern::Title("gnu lesser general public license version 2.1"),
+    ),
+    (
+        "GPL-3.0",
+        "GNU General Public License v3.0",
+        Pattern::Title("gnu general public license version 3"),
+    ),
+    (
+        "GPL-2.0",
+        "GNU General Public License v2.0",
+        Pattern::Title("gnu general public license version 2"),
+    ),
+    (
+        "ISC",
+        "ISC License",
+        Pattern::Phrases(&[
+            "permission to use copy modify and or distribute this software for any purpose",
+        ]),
+    ),
+    (
+        "MIT",
+        "MIT License",
+        Pattern::Phrases(&[
+            "permission is hereby granted free of charge",
+            "the software is provided as is",
+        ]),
+    ),
+    (
+        "MPL-2.0",
+        "Mozilla Public License 2.0",
+        Pattern::Title("mozilla public license version 2.0"),
+    ),
+    (
+        "Unlicense",
+        "The Unlicense",
+        Pattern::Phrases(&[
+            "this is free and unencumbered software released into the public domain",
+        ]),
+    ),
+    (
+        "Zlib",
+        "zlib License",
+        Pattern::Phrases(&[
+            "this software is provided as is without any express or implied warranty",
+            "altered source versions must be plainly marked",
+        ]),
+    ),
+];
+
+pub(super) fn detect_license(content: &str) -> Option<(&'static str, &'static str)> {
+    let title = content
+        .lines()
+        .filter(|l| !normalize(l).is_empty())
+        .take(TITLE_LINES)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let title = format!(" {} ", normalize(&title));
+    let normalized = format!(" {} ", normalize(content));
+    LICENSE_PATTERNS
+        .iter()
+        .find(|(_, _, pattern)| match pattern {
+            Pattern::Phrases(phrases) => phrases
+                .iter()
+                .all(|p| normalized.contains(&format!(" {p} "))),
+            Pattern::Title(t) => title.contains(&format!(" {t} ")),
+        })
+        .map(|(id, name, _)| (*id, *name))
+}
+
+pub(super) fn is_license_file(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    LICENSE_FILE_PREFIXES.iter().any(|p| name.starts_with(p))
+}
+
+fn normalize(content: &str) -> String {
+    content
+        .split(|c: char| !c.is_alphanumeric() && c != '.')
+        .filter(|w| !w.is_empty())
+        .map(|w| w.trim_end_matches('.').to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_license() {
+        let sample = [
+            (
+                "MIT License\n\nPermission is hereby granted, free of charge, to any person\n\
+                 obtaining a copy of this software ...\n\nTHE SOFTWARE IS PROVIDED \"AS IS\", \
+                 WITHOUT WARRANTY OF ANY KIND",
+                Some("MIT"),
+            ),
+            (
+                "                                 Apache License\n\
+                 \x20                          Version 2.0, January 2004",
+                Some("Apache-2.0"),
+            ),
+            (
+                "Redistribution and use in source and binary forms, with or without\n\
+                 modification, are permitted ...\n3. Neither the name of the copyright holder",
+                Some("BSD-3-Clause"),
+            ),
+            (
+                "GNU LESSER GENERAL PUBLIC LICENSE\nVersion 2.1, February 1999",
+                Some("LGPL-2.1"),
+            ),
+            (
+                "GNU GENERAL PUBLIC LICENSE\nVersion 3, 29 June 2007",
+                Some("GPL-3.0"),
+            ),
+            (
+                "                    GNU AFFERO GENERAL PUBLIC LICENSE\n\
+                 \x20                      Version 3, 19 November 2007",
+                Some("AGPL-3.0"),
+            ),
+            (
+                "Boost Software License - Version 1.0 - August 17th, 2003",
+                Some("BSL-1.0"),
+            ),
+            (
+                "Mozilla Public License Version 2.0\n==================================",
+                Some("MPL-2.0"),
+            ),
+            ("All rights reserved.", None),
+        ];
+        for (content, id) in sample.into_iter() {
+            assert_eq!(
+                detect_license(content).map(|(id, _)| id),
+                id,
+                "unexpected license of {content:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_detect_license_full_text() {
+        let sample = [
+            (include_str!("testdata/licenses/GPL-2.0"), "GPL-2.0"),
+            (include_str!("testdata/licenses/GPL-3.0"), "GPL-3.0"),
+            (include_str!("testdata/licenses/LGPL-2.1"), "LGPL-2.1"),
+            (include_str!("testdata/licenses/LGPL-3.0"), "LGPL-3.0"),
+        ];
+        for (content, id) in sample.into_iter() {
+            assert_eq!(detect_license(content).map(|(id, _)| id), Some(id));
+        }
+
+        let gpl = include_str!("testdata/licenses/GPL-3.0");
+        let agpl = gpl
+            .replacen(
+                "GNU GENERAL PUBLIC LICENSE",
+                "GNU AFFERO GENERAL PUBLIC LICENSE",
+                1,
+            )
+            .replacen("29 June 2007", "19 November 2007", 1);
+        assert_eq!(detect_license(&agpl).map(|(id, _)| id), Some("AGPL-3.0"));
+    }
+
+    #[test]
+    fn test_is_license_file() {
+        let sample = [
+            ("LICENSE", true),
+            ("LICENSE-MIT", true),
+            ("license.md", true),
+            ("COPYING", true),
+            ("README.md", false),
+        ];
+        for (name, expected) in sample.into_iter() {
+            assert_eq!(
+                is_license_file(name),
+                expected,
+                "unexpected match of {name}"
+            );
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use time::{format_description::well_known::Rfc2822, OffsetDateTime};
+
+/// One message of `git format-patch` output.
+#[derive(Debug, PartialEq)]
+pub(super) struct MailPatch {
+    pub(super) author_email: String,
+    pub(super) author_name: String,
+    pub(super) date: Option<OffsetDateTime>,
+    pub(super) diff: String,
+    pub(super) message: String,
+}
+
+/// Splits `Name <email>`, falling back to the whole value as the email.
+fn parse_address(value: &str) -> (String, String) {
+    match value.rsplit_once('<') {
+        Some((name, email)) => (
+            name.trim().trim_matches('"').to_string(),
+            email.trim_end().trim_end_matches('>').to_string(),
+        ),
+        None => (String::new(), value.trim().to_string()),
+    }
+}
+
+/// Drops the `[PATCH n/m]` prefix `git format-patch` adds.
+fn strip_subject_prefix(subject: &str) -> &str {
+    match subject.strip_prefix('[') {
+        Some(rest) => rest.split_once("] ").map_or(subject, |(_, s)| s),
+        None => subject,
+    }
+}
+
+fn parse_message(text: &str) -> Option<MailPatch> {
+    let (head, body) = text.split_once("\n\n")?;
+    let mut headers: Vec<(String, String)> = vec![];
+    for line in head.lines() {
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    };
+    let (author_name, author_email) = parse_address(header("from")?);
+    let diff_start = body
+        .find("\ndiff --git ")
+        .map(|i| i + 1)
+        .or_else(|| body.starts_with("diff --git ").then_some(0))?;
+    let (description, diff) = body.split_at(diff_start);
+    let description = match description.split_once("\n---\n") {
+        Some((d, _)) => d,
+        None if description.starts_with("---\n") => "",
+        None => description,
+    }
+    .trim();
+    let diff = match diff.rfind("\n-- \n") {
+        Some(i) if diff[i + 5..].lines().count() <= 2 => &diff[..=i],
+        _ => diff,
+    };
+    let subject = strip_subject_prefix(header("subject").unwrap_or_default());
+    let mut message = subject.to_string();
+    if !description.is_empty() {
+        message.push_str("\n\n");
+        message.push_str(description);
+    }
+    message.push('\n');
+    Some(MailPatch {
+        author_email,
+        author_name,
+        date: header("date").and_then(|d| OffsetDateTime::parse(d, &Rfc2822).ok()),
+        diff: diff.to_string(),
+        message,
+    })
+}
+
+/// Parses an mbox of patches; `None` when any message lacks a `From`
+/// header or a diff.
+pub(super) fn parse_mbox(mbox: &str) -> Option<Vec<MailPatch>> {
+    let mbox = mbox.replace("\r\n", "\n");
+    let mut messages = vec![];
+    let mut current = String::new();
+    for line in mbox.split_inclusive('\n') {
+        if line.starts_with("From ") && !current.trim().is_empty() {
+            messages.push(std::mem::take(&mut current));
+        }
+        if !(line.starts_with("From ") && current.is_empty()) {
+            current.push_str(line);
+        }
+    }
+    if !current.trim().is_empty() {
+        messages.push(current);
+    }
+    if messages.is_empty() {
+        return None;
+    }
+    messages.iter().map(|m| parse_message(m)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mbox() {
+        let patch = |n: usize| {
+            format!(
+                "From 0123456789abcdef0123456789abcdef01234567 Mon Sep 17 00:00:00 2001\n\
+                 From: \"Jane Doe\" <jane@example.com>\n\
+                 Date: Tue, 1 Oct 2024 12:00:00 +0200\n\
+                 Subject: [PATCH {n}/2] Fix the\n \
+                 thing\n\
+                 \n\
+                 Longer description.\n\
+                 ---\n \
+                 README.md | 2 +-\n\
+                 \n\
+                 diff --git a/README.md b/README.md\n\
+                 --- a/README.md\n\
+                 +++ b/README.md\n\
+                 @@ -1 +1 @@\n\
+                 -wit\n\
+                 +wit {n}\n\
+                 -- \n\
+                 2.47.0\n\
+                 \n"
+            )
+        };
+        let patches = parse_mbox(&format!("{}{}", patch(1), patch(2)))
+            .unwrap_or_else(|| panic!("mbox should parse"));
+        assert_eq!(patches.len(), 2);
+        let first = &patches[0];
+        assert_eq!(first.author_name, "Jane Doe");
+        assert_eq!(first.author_email, "jane@example.com");
+        assert_eq!(first.message, "Fix the thing\n\nLonger description.\n");
+        assert_eq!(first.date.map(|d| d.unix_timestamp()), Some(1727776800));
+        assert!(first
+            .diff
+            .starts_with("diff --git a/README.md b/README.md\n"));
+        assert!(first.diff.ends_with("+wit 1\n"));
+
+        let bare = patch(1).replace("Longer description.\n", "");
+        let patches = parse_mbox(&bare).unwrap_or_else(|| panic!("mbox should parse"));
+        assert_eq!(patches[0].message, "Fix the thing\n");
+
+        assert_eq!(parse_mbox(""), None);
+        assert_eq!(parse_mbox("From: a@example.com\n\nno diff here\n"), None);
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+const BUCKETS: [f64; 12] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum GitOperation {
+    Blame,
+    Diff,
+    Open,
+    Revwalk,
+    TreeWalk,
+}
+
+impl GitOperation {
+    const ALL: [GitOperation; 5] = [
+        GitOperation::Blame,
+        GitOperation::Diff,
+        GitOperation::Open,
+        GitOperation::Revwalk,
+        GitOperation::TreeWalk,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            GitOperation::Blame => "blame",
+            GitOperation::Diff => "diff",
+            GitOperation::Open => "open",
+            GitOperation::Revwalk => "revwalk",
+            GitOperation::TreeWalk => "tree_walk",
+        }
+    }
+}
+
+struct Histogram {
+    buckets: [AtomicU64; BUCKETS.len()],
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        Histogram {
+            buckets: [const { AtomicU64::new(0) }; BUCKETS.len()],
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+
+    fn observe(&self, seconds: f64) {
+        if let Some(i) = BUCKETS.iter().position(|&b| seconds <= b) {
+            self.buckets[i].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add((seconds * 1e6) as u64, Ordering::Relaxed);
+    }
+}
+
+static HISTOGRAMS: [Histogram; GitOperation::ALL.len()] =
+    [const { Histogram::new() }; GitOperation::ALL.len()];
+
+fn histogram(op: GitOperation) -> &'static Histogram {
+    &HISTOGRAMS[op as usize]
+}
+
+/// Records the time until it is dropped against `op`.
+pub(crate) struct Timer {
+    op: GitOperation,
+    start: Instant,
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        histogram(self.op).observe(self.start.elapsed().as_secs_f64());
+    }
+}
+
+pub(crate) fn start_timer(op: GitOperation) -> Timer {
+    Timer {
+        op,
+        start: Instant::now(),
+    }
+}
+
+/// Renders the histograms in the Prometheus text exposition format.
+pub(crate) fn render_metrics() -> String {
+    let mut out = String::from(
+        "# HELP wit_git_operation_duration_seconds Time spent in git operations.\n\
+         # TYPE wit_git_operation_duration_seconds histogram\n",
+    );
+    for op in GitOperation::ALL {
+        let h = histogram(op);
+        let name = op.name();
+        let mut cumulative = 0;
+        for (bound, count) in BUCKETS.iter().zip(h.buckets.iter()) {
+            cumulative += count.load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "wit_git_operation_duration_seconds_bucket{{operation=\"{name}\",le=\"{bound}\"}} {cumulative}"
+            );
+        }
+        let count = h.count.load(Ordering::Relaxed);
+        let sum = h.sum_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = writeln!(
+            out,
+            "wit_git_operation_duration_seconds_bucket{{operation=\"{name}\",le=\"+Inf\"}} {count}\n\
+             wit_git_operation_duration_seconds_sum{{operation=\"{name}\"}} {sum}\n\
+             wit_git_operation_duration_seconds_count{{operation=\"{name}\"}} {count}"
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        drop(start_timer(GitOperation::Blame));
+        let metrics = render_metrics();
+        assert!(metrics.contains(
+            "wit_git_operation_duration_seconds_bucket{operation=\"blame\",le=\"0.001\"}"
+        ));
+        let count = metrics
+            .lines()
+            .find_map(|l| {
+                l.strip_prefix("wit_git_operation_duration_seconds_count{operation=\"blame\"} ")
+            })
+            .and_then(|n| n.parse::<u64>().ok())
+            .unwrap_or_default();
+        assert!(count >= 1);
+    }
+}
@@ -1,22 +1,160 @@
+mod archive;
+mod attributes;
+mod avatar;
+mod batch;
+mod blame;
+mod budget;
+mod codeowners;
+mod deadline;
+mod diff;
+mod encoding;
 mod error;
+mod language;
+mod license;
+mod mailbox;
+mod metrics;
 pub(crate) mod model;
-
-use std::{collections::HashSet, path::Path};
+mod notify;
+mod pack;
+mod settings;
+mod sidecar;
+mod sort;
+mod submodule;
+mod summary;
+mod write;
+
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
+    path::Path,
+    sync::Arc,
+};
 
 use git2::{
-    Blob, Branch, Commit, ErrorClass, ErrorCode, IndexEntry, Object, ObjectType, Oid, Reference,
-    Repository, Time, Tree, TreeEntry, TreeWalkMode, TreeWalkResult,
+    BlameOptions, Blob, Branch, BranchType, Commit, Delta, DescribeFormatOptions, DescribeOptions,
+    Diff, Direction, ErrorClass, ErrorCode, IndexEntry, Mailmap, Object, ObjectType, Odb, Oid,
+    Reference, Repository, Signature, Time, Tree, TreeEntry, TreeWalkMode, TreeWalkResult,
+    Worktree,
 };
+use serde::Deserialize;
 use time::{OffsetDateTime, UtcOffset};
 
+use self::attributes::TreeAttributes;
+pub(crate) use self::batch::BlobSpec;
+pub(crate) use self::blame::BlameCache;
+pub(crate) use self::deadline::{CancelOnDrop, Deadline};
+pub(crate) use self::diff::{DiffOptions, IgnoreWhitespace};
 pub(crate) use self::error::{GitError, GitResult};
+pub(crate) use self::metrics::render_metrics;
+use self::metrics::GitOperation;
 use self::model::{
-    GitBlob, GitBlobContent, GitBranch, GitCommit, GitIndex, GitIndexDirectory, GitIndexEntry,
-    GitOid, GitReference, GitRemote, GitStatus, GitTag, GitTree, GitUpstream, MaybeLossyUtf8,
+    GitBlameHunk, GitBlob, GitBlobContent, GitBranch, GitBranchDivergence, GitBranchType,
+    GitCherry, GitCodeOwner, GitCommit, GitCommitDetail, GitCommitSignature, GitCommitTags,
+    GitDiffFile, GitDiffStats, GitIndex, GitIndexDirectory, GitIndexEntry, GitLanguageStats,
+    GitLicense, GitManifest, GitManifestEntry, GitMerge, GitObject, GitObjectType, GitOid,
+    GitReadme, GitRefUpdate, GitReference, GitRemote, GitRemoteRef, GitRemoteRefStatus,
+    GitRepositoryState, GitSignature, GitStatus, GitSummary, GitTag, GitTree, GitTreeStats,
+    GitUnreleased, GitUpstream, GitWorktree, MaybeLossyUtf8,
 };
+pub(crate) use self::settings::{GitSettings, RepoHandle};
+pub(crate) use self::sidecar::{NotifyConfig, RepoConfig};
+pub(crate) use self::sort::{SortDirection, SortKey, SortOptions};
+pub(crate) use self::summary::SummaryCache;
+pub(crate) use self::write::MergeStrategy;
+
+const MAX_COMMIT_COUNT: usize = 1000;
+const MAX_PICKAXE_COMMITS: usize = 10_000;
+pub(crate) const MAX_MANIFEST_ENTRIES: usize = 10_000;
+
+pub(crate) fn libgit2_version() -> String {
+    let (major, minor, rev) = git2::Version::get().libgit2_version();
+    format!("{major}.{minor}.{rev}")
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct ListBranchOptions {
+    pub(crate) contains: Option<GitOid>,
+    #[serde(rename = "type")]
+    pub(crate) kind: Option<GitBranchType>,
+}
+
+#[derive(Debug)]
+pub(crate) struct ListCommitOptions {
+    pub(crate) exclude: Option<GitOid>,
+    pub(crate) limit: usize,
+    pub(crate) mailmap: bool,
+    pub(crate) offset: usize,
+    pub(crate) start: Option<GitOid>,
+    pub(crate) until: Option<GitOid>,
+    pub(crate) with_stats: bool,
+}
+
+impl Default for ListCommitOptions {
+    fn default() -> Self {
+        ListCommitOptions {
+            exclude: None,
+            limit: MAX_COMMIT_COUNT,
+            mailmap: true,
+            offset: 0,
+            start: None,
+            until: None,
+            with_stats: false,
+        }
+    }
+}
+
+/// `pattern` is a glob matched against the reference name with its
+/// `refs/<namespace>/` prefix removed, e.g. `release/*` or `v1.*`.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct ListReferenceOptions {
+    pub(crate) namespace: Option<RefNamespace>,
+    #[serde(rename = "match")]
+    pub(crate) pattern: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum RefNamespace {
+    Heads,
+    Notes,
+    Remotes,
+    Tags,
+}
+
+impl RefNamespace {
+    fn prefix(&self) -> &'static str {
+        match self {
+            RefNamespace::Heads => "refs/heads/",
+            RefNamespace::Notes => "refs/notes/",
+            RefNamespace::Remotes => "refs/remotes/",
+            RefNamespace::Tags => "refs/tags/",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct SearchCommitOptions {
+    pub(crate) follow: bool,
+    pub(crate) mailmap: bool,
+    pub(crate) path: Option<String>,
+    pub(crate) pickaxe: Option<String>,
+}
+
+impl Default for SearchCommitOptions {
+    fn default() -> Self {
+        SearchCommitOptions {
+            follow: false,
+            mailmap: true,
+            path: None,
+            pickaxe: None,
+        }
+    }
+}
 
 pub(crate) struct GitRepository {
+    deadline: Deadline,
     repo: Repository,
+    settings: Arc<GitSettings>,
 }
 
 impl std::fmt::Debug for GitRepository {
@@ -27,7 +165,11 @@ impl std::fmt::Debug for GitRepository {
 
 impl From<Repository> for GitRepository {
     fn from(r: Repository) -> Self {
-        GitRepository { repo: r }
+        GitRepository {
+            deadline: Deadline::default(),
+            repo: r,
+            settings: Arc::default(),
+        }
     }
 }
 
@@ -109,7 +251,347 @@ impl ShortIdGetter for Reference<'_> {
     }
 }
 
+fn merged_branch(message: &str) -> Option<String> {
+    let summary = message.lines().next()?;
+    if let Some(rest) = summary
+        .strip_prefix("Merge branch '")
+        .or_else(|| summary.strip_prefix("Merge remote-tracking branch '"))
+    {
+        return rest.split_once('\'').map(|(branch, _)| branch.to_string());
+    }
+    let rest = summary.strip_prefix("Merge pull request ")?;
+    let (_, branch) = rest.split_once(" from ")?;
+    branch.split_whitespace().next().map(str::to_string)
+}
+
+#[derive(Default)]
+struct TreeStats {
+    file_count: usize,
+    languages: HashMap<String, (usize, u64)>,
+    size: u64,
+}
+
+impl TreeStats {
+    fn add_blob(&mut self, attributes: &TreeAttributes, odb: &Odb<'_>, path: &str, id: Oid) {
+        let size = odb
+            .read_header(id)
+            .map(|(size, _)| size as u64)
+            .unwrap_or_default();
+        self.file_count += 1;
+        self.size += size;
+        let linguist = attributes.linguist(path);
+        if linguist.excluded {
+            return;
+        }
+        let language = linguist
+            .language
+            .or_else(|| language::detect_language(path).map(str::to_string));
+        if let Some(language) = language {
+            let entry = self.languages.entry(language).or_default();
+            entry.0 += 1;
+            entry.1 += size;
+        }
+    }
+
+    fn add_tree(
+        &mut self,
+        attributes: &TreeAttributes,
+        odb: &Odb<'_>,
+        root: &str,
+        tree: &Tree<'_>,
+        deadline: &Deadline,
+    ) -> GitResult<()> {
+        let result = tree.walk(TreeWalkMode::PreOrder, |parent, entry| {
+            if deadline.is_expired() {
+                return TreeWalkResult::Abort;
+            }
+            if let Some(ObjectType::Blob) = entry.kind() {
+                let path = format!("{root}{parent}{}", entry.name().unwrap_or_default());
+                self.add_blob(attributes, odb, &path, entry.id());
+            }
+            TreeWalkResult::Ok
+        });
+        deadline.check()?;
+        Ok(result?)
+    }
+
+    fn merge(mut self, other: TreeStats) -> TreeStats {
+        self.file_count += other.file_count;
+        self.size += other.size;
+        for (language, (file_count, size)) in other.languages {
+            let entry = self.languages.entry(language).or_default();
+            entry.0 += file_count;
+            entry.1 += size;
+        }
+        self
+    }
+}
+
+impl From<TreeStats> for GitTreeStats {
+    fn from(s: TreeStats) -> Self {
+        let mut languages = s
+            .languages
+            .into_iter()
+            .map(|(name, (file_count, size))| GitLanguageStats {
+                file_count,
+                name,
+                size,
+            })
+            .collect::<Vec<_>>();
+        languages.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        GitTreeStats {
+            file_count: s.file_count,
+            languages,
+            license: None,
+            size: s.size,
+        }
+    }
+}
+
 impl GitRepository {
+    fn ahead_behind(&self, target: &GitOid, other: &Branch<'_>) -> GitResult<GitUpstream> {
+        let id = other.get_id();
+        let (ahead, behind) = self.repo.graph_ahead_behind(target.0, id.0)?;
+        Ok(GitUpstream {
+            ahead,
+            behind,
+            name: other.get().name_bytes().into(),
+            shorthand: other.name_bytes().unwrap_or_default().into(),
+            target: id,
+            target_short: other.get_short_id(),
+        })
+    }
+
+    fn contains_commit(&self, target: Oid, id: Oid) -> bool {
+        target == id
+            || self
+                .repo
+                .merge_base(target, id)
+                .is_ok_and(|base| base == id)
+    }
+
+    fn count_between(&self, id: Oid, target: Oid) -> GitResult<usize> {
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push(target)?;
+        revwalk.hide(id)?;
+        let mut count = 0;
+        for _ in revwalk {
+            self.deadline.check()?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn count_occurrences(&self, id: Oid, needle: &[u8]) -> usize {
+        if id.is_zero() {
+            return 0;
+        }
+        let Ok(blob) = self.repo.find_blob(id) else {
+            return 0;
+        };
+        let content = blob.content();
+        let mut count = 0;
+        let mut i = 0;
+        while i + needle.len() <= content.len() {
+            if content[i..].starts_with(needle) {
+                count += 1;
+                i += needle.len();
+            } else {
+                i += 1;
+            }
+        }
+        count
+    }
+
+    fn abbrev_len(&self) -> usize {
+        self.repo
+            .config()
+            .and_then(|c| c.get_i32("core.abbrev"))
+            .ok()
+            .and_then(|n| usize::try_from(n).ok())
+            .map(|n| n.clamp(4, 40))
+            .unwrap_or(7)
+    }
+
+    /// Archives the subtree at `path`, or the whole tree when it is empty.
+    pub(crate) fn archive(&self, reference: &str, path: &str, prefix: &str) -> GitResult<Vec<u8>> {
+        let commit = self.repo.revparse_single(reference)?.peel_to_commit()?;
+        let tree = match path.trim_matches('/') {
+            "" => commit.tree()?,
+            path => commit
+                .tree()?
+                .get_path(Path::new(path))?
+                .to_object(&self.repo)?
+                .peel_to_tree()?,
+        };
+        archive::write_archive(self, &tree, prefix, commit.time().seconds())
+    }
+
+    pub(crate) fn blame_file(
+        &self,
+        id: &GitOid,
+        path: &str,
+        mailmap: bool,
+    ) -> GitResult<Vec<GitBlameHunk>> {
+        let _timer = metrics::start_timer(GitOperation::Blame);
+        let mut opts = BlameOptions::new();
+        opts.newest_commit(id.0).use_mailmap(mailmap);
+        let blame = self.repo.blame_file(Path::new(path), Some(&mut opts))?;
+        Ok(blame
+            .iter()
+            .map(|h| {
+                let author = h.final_signature();
+                GitBlameHunk {
+                    commit_id: h.final_commit_id().into(),
+                    line_count: h.lines_in_hunk(),
+                    start_line: h.final_start_line(),
+                    time: author.when().datetime(),
+                    author: self.convert_to_signature(&author, None),
+                }
+            })
+            .collect())
+    }
+
+    /// Compares a local branch against its upstream and against the default
+    /// branch.
+    pub(crate) fn branch_divergence(&self, name: &str) -> GitResult<GitBranchDivergence> {
+        let branch = self.repo.find_branch(name, BranchType::Local)?;
+        let target = branch.get_id();
+        let default_branch = self
+            .default_reference()
+            .ok()
+            .filter(Reference::is_branch)
+            .map(Branch::wrap)
+            .map(|b| self.ahead_behind(&target, &b))
+            .transpose()?;
+        Ok(GitBranchDivergence {
+            default_branch,
+            name: branch.get().name_bytes().into(),
+            shorthand: branch.name_bytes().unwrap_or_default().into(),
+            target_short: branch.get_short_id(),
+            upstream: branch
+                .upstream()
+                .ok()
+                .map(|u| self.ahead_behind(&target, &u))
+                .transpose()?,
+            target,
+        })
+    }
+
+    pub(crate) fn compare(
+        &self,
+        base: &str,
+        head: &str,
+        options: DiffOptions,
+    ) -> GitResult<Vec<GitDiffFile>> {
+        let _timer = metrics::start_timer(GitOperation::Diff);
+        let base_tree = self.repo.revparse_single(base)?.peel_to_commit()?.tree()?;
+        let head_tree = self.repo.revparse_single(head)?.peel_to_commit()?.tree()?;
+        let diff = self.repo.diff_tree_to_tree(
+            Some(&base_tree),
+            Some(&head_tree),
+            Some(&mut (&options).into()),
+        )?;
+        diff::convert_diff(self, &diff, &options)
+    }
+
+    /// Returns the most recent tag reachable from `id`, if any.
+    pub(crate) fn describe_tag(&self, id: &GitOid) -> GitResult<Option<String>> {
+        let commit = self.repo.find_commit(id.0)?;
+        let describe = match commit
+            .as_object()
+            .describe(DescribeOptions::new().describe_tags())
+        {
+            Ok(describe) => describe,
+            Err(e) if e.class() == ErrorClass::Describe => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Some(describe.format(Some(
+            DescribeFormatOptions::new().abbreviated_size(0),
+        ))?))
+    }
+
+    pub(crate) fn diff_commit(
+        &self,
+        id: GitOid,
+        options: DiffOptions,
+    ) -> GitResult<Vec<GitDiffFile>> {
+        let _timer = metrics::start_timer(GitOperation::Diff);
+        let commit = self.repo.find_commit(id.0)?;
+        let diff = self.diff_to_parent(&commit, Some(&mut (&options).into()))?;
+        diff::convert_diff(self, &diff, &options)
+    }
+
+    fn diff_to_parent(
+        &self,
+        commit: &Commit<'_>,
+        opts: Option<&mut git2::DiffOptions>,
+    ) -> GitResult<Diff<'_>> {
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        Ok(self
+            .repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), opts)?)
+    }
+
+    fn diff_stats(&self, commit: &Commit<'_>) -> GitResult<GitDiffStats> {
+        let stats = self.diff_to_parent(commit, None)?.stats()?;
+        Ok(GitDiffStats {
+            deletions: stats.deletions(),
+            files_changed: stats.files_changed(),
+            insertions: stats.insertions(),
+        })
+    }
+
+    fn fetched_at(&self, remote: &str) -> Option<OffsetDateTime> {
+        self.repo
+            .references_glob(&format!("refs/remotes/{remote}/*"))
+            .ok()?
+            .flatten()
+            .filter_map(|r| self.reflog_time(r.name()?))
+            .max()
+    }
+
+    fn reference_targets(&self) -> GitResult<HashMap<String, Oid>> {
+        Ok(self
+            .repo
+            .references()?
+            .flatten()
+            .filter_map(|r| Some((r.name()?.to_string(), r.target()?)))
+            .collect())
+    }
+
+    /// Returns the refs the fetch created or moved.
+    pub(crate) fn fetch_remote(&self, name: &str) -> GitResult<Vec<GitRefUpdate>> {
+        let mut remote = self.repo.find_remote(name)?;
+        let before = self.reference_targets()?;
+        let refspecs: [&str; 0] = [];
+        remote.fetch(&refspecs, None, None)?;
+        let mut updates = vec![];
+        for (name, new) in self.reference_targets()? {
+            let old = before.get(&name).copied();
+            if old == Some(new) {
+                continue;
+            }
+            let shorthand = self
+                .repo
+                .find_reference(&name)
+                .ok()
+                .and_then(|r| r.shorthand().map(str::to_string))
+                .unwrap_or_else(|| name.clone());
+            updates.push(GitRefUpdate {
+                name,
+                new: new.into(),
+                old: old.map(Into::into),
+                shorthand,
+            });
+        }
+        updates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(updates)
+    }
+
     pub(crate) fn gather_status(&self) -> GitResult<Vec<GitStatus>> {
         Ok(self
             .repo
@@ -123,69 +605,439 @@ impl GitRepository {
             .collect())
     }
 
-    pub(crate) fn get_blob(&self, oid: GitOid) -> GitResult<GitBlob> {
+    pub(crate) fn gather_tree_stats(&self) -> GitResult<GitTreeStats> {
+        let _timer = metrics::start_timer(GitOperation::TreeWalk);
+        let root = self.default_reference()?.peel_to_tree()?;
+        let odb = self.repo.odb()?;
+        let attributes = TreeAttributes::new(&self.repo, &root)?;
+        let mut stats = TreeStats::default();
+        let mut subtrees = vec![];
+        for entry in root.iter() {
+            let name = String::from_utf8_lossy(entry.name_bytes());
+            match entry.kind() {
+                Some(ObjectType::Blob) => stats.add_blob(&attributes, &odb, &name, entry.id()),
+                Some(ObjectType::Tree) => subtrees.push((format!("{name}/"), entry.id())),
+                _ => {}
+            }
+        }
+        let workers = std::thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(1)
+            .min(subtrees.len());
+        let path = self.repo.path();
+        let deadline = &self.deadline;
+        let subtrees = &subtrees;
+        let worker_attributes = (0..workers)
+            .map(|_| attributes.try_clone())
+            .collect::<GitResult<Vec<_>>>()?;
+        let partials = std::thread::scope(|scope| {
+            worker_attributes
+                .into_iter()
+                .enumerate()
+                .map(|(n, attributes)| {
+                    scope.spawn(move || -> GitResult<TreeStats> {
+                        let repo = Repository::open(path)?;
+                        let odb = repo.odb()?;
+                        let mut stats = TreeStats::default();
+                        for (root, id) in subtrees.iter().skip(n).step_by(workers) {
+                            let tree = repo.find_tree(*id)?;
+                            stats.add_tree(&attributes, &odb, root, &tree, deadline)?;
+                        }
+                        Ok(stats)
+                    })
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|h| h.join().unwrap_or_else(|p| std::panic::resume_unwind(p)))
+                .collect::<GitResult<Vec<_>>>()
+        })?;
+        let mut stats: GitTreeStats = partials.into_iter().fold(stats, TreeStats::merge).into();
+        let mut licenses = self
+            .list_license()?
+            .into_iter()
+            .map(|l| l.spdx_id)
+            .collect::<Vec<_>>();
+        licenses.sort_unstable();
+        licenses.dedup();
+        if !licenses.is_empty() {
+            stats.license = Some(licenses.join(" OR "));
+        }
+        Ok(stats)
+    }
+
+    /// Whether the blob is binary follows the attributes of `path`, from the
+    /// tree of `at` when given and the working tree and index otherwise.
+    pub(crate) fn get_blob(
+        &self,
+        oid: GitOid,
+        path: Option<&str>,
+        at: Option<&GitOid>,
+        max_size: usize,
+    ) -> GitResult<GitBlob> {
+        let (size, _) = self.repo.odb()?.read_header(oid.0)?;
+        self.check_budget(
+            format_args!("blob {oid} ({size} bytes)"),
+            size,
+            "fetch it from the raw blob endpoint instead",
+        )?;
+        let binary_attribute = match (path, at) {
+            (Some(path), Some(at)) => {
+                let tree = self.repo.find_object(at.0, None)?.peel_to_tree()?;
+                TreeAttributes::for_path(&self.repo, &tree, path)?.binary(path)
+            }
+            (Some(path), None) => attributes::binary_attribute(&self.repo, path),
+            (None, _) => None,
+        };
         Ok(self.repo.find_blob(oid.0).map(|b| {
-            let content = match b.is_binary() {
-                true => GitBlobContent::Binary(b.content().into()),
-                false => GitBlobContent::Text(b.content().into()),
+            let is_binary = binary_attribute
+                .unwrap_or_else(|| b.is_binary() && !encoding::has_bom(b.content()));
+            let (content, encoding, has_crlf, line_count, truncated) = if is_binary {
+                let data = encoding::truncate(b.content(), max_size);
+                let truncated = data.len() < b.size();
+                (
+                    GitBlobContent::Binary(data.into()),
+                    None,
+                    false,
+                    0,
+                    truncated,
+                )
+            } else {
+                let (text, encoding) = encoding::decode(b.content());
+                let data = encoding::truncate(text.as_bytes(), max_size);
+                (
+                    GitBlobContent::Text(data.into()),
+                    Some(encoding.to_string()),
+                    encoding::has_crlf(text.as_bytes()),
+                    encoding::count_lines(text.as_bytes()),
+                    data.len() < text.len(),
+                )
             };
             GitBlob {
                 content,
+                encoding,
+                has_crlf,
                 id: b.id().into(),
-                is_binary: b.is_binary(),
+                is_binary,
+                line_count,
+                raw_url: truncated.then(|| format!("/api/v1/git/blobs/{}/raw", b.id())),
                 short_id: b.get_short_id(),
                 size: b.size(),
+                truncated,
             }
         })?)
     }
 
+    pub(crate) fn get_commit(&self, id: GitOid, mailmap: bool) -> GitResult<GitCommitDetail> {
+        let mailmap = self.mailmap(mailmap);
+        let commit = self.repo.find_commit(id.0)?;
+        let merge = match commit.parent(1) {
+            Ok(parent) => Some(GitMerge {
+                branch: merged_branch(&String::from_utf8_lossy(commit.message_bytes())),
+                id: parent.id().into(),
+                short_id: parent.get_short_id(),
+                summary: parent.summary_bytes().unwrap_or_default().into(),
+            }),
+            Err(_) => None,
+        };
+        let signature = match self.repo.extract_signature(&commit.id(), None) {
+            Ok((signature, _)) => Some(GitCommitSignature::from(&signature[..])),
+            Err(e) if e.code() == ErrorCode::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let mut detail = self.convert_to_commit(&commit, mailmap.as_ref());
+        detail.truncated_history = self.shallow_boundary().contains(&commit.id());
+        if !detail.truncated_history {
+            detail.stats = Some(self.diff_stats(&commit)?);
+        }
+        Ok(GitCommitDetail {
+            commit: detail,
+            merge,
+            signature,
+        })
+    }
+
+    pub(crate) fn get_path_id(&self, id: &GitOid, path: &str) -> GitResult<GitOid> {
+        let tree = self.repo.find_commit(id.0)?.tree()?;
+        Ok(tree.get_path(Path::new(path))?.id().into())
+    }
+
+    /// Fails unless `oid` exists and, when given, is of type `kind`, without
+    /// reading its content.
+    pub(crate) fn check_object(&self, oid: &GitOid, kind: Option<GitObjectType>) -> GitResult<()> {
+        let (_, found) = self.repo.odb()?.read_header(oid.0)?;
+        if kind.is_some_and(|k| k != found.into()) {
+            return Err(GitError::ObjectNotFound(format!("{oid} is a {found}")));
+        }
+        Ok(())
+    }
+
+    /// Trees are printed one `mode type id\tname` entry per line; other
+    /// objects as their stored content.
+    pub(crate) fn get_object(&self, oid: GitOid) -> GitResult<GitObject> {
+        let odb = self.repo.odb()?;
+        let (size, kind) = odb.read_header(oid.0)?;
+        self.check_budget(
+            format_args!("object {oid} ({size} bytes)"),
+            size,
+            "fetch it from the raw object endpoint instead",
+        )?;
+        let content = match kind {
+            ObjectType::Tree => self
+                .repo
+                .find_tree(oid.0)?
+                .iter()
+                .map(|e| {
+                    format!(
+                        "{:06o} {} {}\t{}\n",
+                        e.filemode(),
+                        e.kind().map_or("unknown", |k| k.str()),
+                        e.id(),
+                        String::from_utf8_lossy(e.name_bytes())
+                    )
+                })
+                .collect::<String>()
+                .into(),
+            _ => odb.read(oid.0)?.data().into(),
+        };
+        Ok(GitObject {
+            content,
+            id: oid,
+            kind: kind.into(),
+            size,
+        })
+    }
+
+    pub(crate) fn get_raw_blob(&self, oid: GitOid) -> GitResult<Vec<u8>> {
+        let (size, _) = self.repo.odb()?.read_header(oid.0)?;
+        self.check_budget(
+            format_args!("blob {oid} ({size} bytes)"),
+            size,
+            "clone the repository to read it",
+        )?;
+        Ok(self.repo.find_blob(oid.0)?.content().into())
+    }
+
+    pub(crate) fn get_raw_object(&self, oid: GitOid) -> GitResult<Vec<u8>> {
+        let odb = self.repo.odb()?;
+        let (size, _) = odb.read_header(oid.0)?;
+        self.check_budget(
+            format_args!("object {oid} ({size} bytes)"),
+            size,
+            "clone the repository to read it",
+        )?;
+        let data = odb.read(oid.0)?.data().to_vec();
+        Ok(data)
+    }
+
+    pub(crate) fn head_id(&self) -> GitResult<GitOid> {
+        Ok(self.default_reference()?.peel_to_commit()?.id().into())
+    }
+
+    pub(crate) fn head_shorthand(&self) -> GitResult<String> {
+        Ok(String::from_utf8_lossy(self.default_reference()?.shorthand_bytes()).into_owned())
+    }
+
+    /// Whether objects may be missing locally and fetched on demand from a
+    /// promisor remote, as in `git clone --filter`.
+    fn is_partial_clone(&self) -> bool {
+        let Ok(config) = self.repo.config() else {
+            return false;
+        };
+        config.get_string("extensions.partialclone").is_ok()
+            || config
+                .entries(Some(r"remote\..*\.promisor"))
+                .is_ok_and(|mut entries| {
+                    let mut promisor = false;
+                    while let Some(Ok(entry)) = entries.next() {
+                        promisor |= entry.value() == Some("true");
+                    }
+                    promisor
+                })
+    }
+
     pub(crate) fn list_branch(&self) -> GitResult<Vec<GitBranch>> {
-        Ok(self
-            .repo
-            .branches(None)?
+        self.list_branch_matching(&ListBranchOptions::default())
+    }
+
+    pub(crate) fn list_branch_containing(&self, id: GitOid) -> GitResult<Vec<GitBranch>> {
+        self.list_branch_matching(&ListBranchOptions {
+            contains: Some(id),
+            kind: None,
+        })
+    }
+
+    pub(crate) fn list_branch_matching(
+        &self,
+        opts: &ListBranchOptions,
+    ) -> GitResult<Vec<GitBranch>> {
+        if let Some(id) = &opts.contains {
+            self.repo.find_commit(id.0)?;
+        }
+        let mut local = HashSet::new();
+        for (b, _) in self.repo.branches(Some(BranchType::Local))?.flatten() {
+            local.insert(b.name_bytes().unwrap_or_default().to_vec());
+            if let Ok(upstream) = b.upstream() {
+                local.insert(upstream.get().name_bytes().to_vec());
+            }
+        }
+        self.repo
+            .branches(opts.kind.map(Into::into))?
             .flatten()
-            .map(|(b, t)| GitBranch {
-                kind: t.into(),
-                name: b.get().name_bytes().into(),
-                shorthand: b.name_bytes().unwrap_or_default().into(),
-                target: b.get_id(),
-                target_short: b.get_short_id(),
-                upstream: b
-                    .upstream()
-                    .map(|u| {
-                        Some(GitUpstream {
-                            name: u.get().name_bytes().into(),
-                            shorthand: u.name_bytes().unwrap_or_default().into(),
-                            target: u.get_id(),
-                            target_short: u.get_short_id(),
-                        })
-                    })
-                    .unwrap_or_default(),
+            .filter(|(b, _)| {
+                opts.contains
+                    .as_ref()
+                    .is_none_or(|id| self.contains_commit(b.get_id().0, id.0))
+            })
+            .map(|(b, t)| {
+                let target = b.get_id();
+                let refname = b.get().name().unwrap_or_default();
+                let remote = match t {
+                    BranchType::Local => None,
+                    BranchType::Remote => self.repo.branch_remote_name(refname).ok(),
+                };
+                let remote_only = remote.as_ref().is_some_and(|r| {
+                    let shorthand = b.name_bytes().unwrap_or_default();
+                    let name = shorthand.strip_prefix(&r[..]).unwrap_or(shorthand);
+                    let name = name.strip_prefix(b"/").unwrap_or(name);
+                    !local.contains(name) && !local.contains(b.get().name_bytes())
+                });
+                Ok(GitBranch {
+                    fetched_at: self.reflog_time(refname),
+                    kind: t.into(),
+                    name: b.get().name_bytes().into(),
+                    remote: remote.map(|r| r[..].into()),
+                    remote_only,
+                    shorthand: b.name_bytes().unwrap_or_default().into(),
+                    target_short: b.get_short_id(),
+                    tip: b
+                        .get()
+                        .peel_to_commit()
+                        .ok()
+                        .map(|c| self.convert_to_commit(&c, None)),
+                    upstream: b
+                        .upstream()
+                        .ok()
+                        .map(|u| self.ahead_behind(&target, &u))
+                        .transpose()?,
+                    target,
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn list_cherry(
+        &self,
+        upstream: &str,
+        head: &str,
+        mailmap: bool,
+    ) -> GitResult<Vec<GitCherry>> {
+        let mailmap = self.mailmap(mailmap);
+        let upstream = self.resolve_commit(upstream)?.0;
+        let head = self.resolve_commit(head)?.0;
+        let patch_ids = |from: Oid, hide: Oid| -> GitResult<Vec<(Oid, Oid)>> {
+            let mut revwalk = self.repo.revwalk()?;
+            revwalk.push(from)?;
+            revwalk.hide(hide)?;
+            let mut ids = vec![];
+            for id in revwalk.flatten().take(MAX_COMMIT_COUNT) {
+                self.deadline.check()?;
+                let commit = self.repo.find_commit(id)?;
+                if commit.parent_count() > 1 {
+                    continue;
+                }
+                ids.push((self.diff_to_parent(&commit, None)?.patchid(None)?, id));
+            }
+            Ok(ids)
+        };
+        let upstream_ids = patch_ids(upstream, head)?
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let mut cherries = vec![];
+        for (patch_id, id) in patch_ids(head, upstream)?.into_iter().rev() {
+            let commit = self.repo.find_commit(id)?;
+            cherries.push(GitCherry {
+                commit: self.convert_to_commit(&commit, mailmap.as_ref()),
+                equivalent: upstream_ids.get(&patch_id).map(|&id| id.into()),
+            });
+        }
+        Ok(cherries)
+    }
+
+    pub(crate) fn list_code_owner(&self, path: &str) -> GitResult<Vec<GitCodeOwner>> {
+        let root = self.default_reference()?.peel_to_tree()?;
+        let Some((source, blob)) = codeowners::CODEOWNERS_PATHS.iter().find_map(|p| {
+            let entry = root.get_path(Path::new(p)).ok()?;
+            Some((p, self.repo.find_blob(entry.id()).ok()?))
+        }) else {
+            return Ok(vec![]);
+        };
+        let rules = codeowners::parse_rules(&String::from_utf8_lossy(blob.content()));
+        Ok(codeowners::match_rules(&rules, path)
+            .into_iter()
+            .map(|r| GitCodeOwner {
+                line_number: r.line_number,
+                owners: r.owners.clone(),
+                path: source.to_string(),
+                pattern: r.pattern.clone(),
             })
             .collect())
     }
 
-    pub(crate) fn list_commit(&self) -> GitResult<Vec<GitCommit>> {
+    pub(crate) fn list_commit(&self, options: ListCommitOptions) -> GitResult<Vec<GitCommit>> {
+        let limit = options.limit.min(MAX_COMMIT_COUNT);
+        self.walk_commits(options, limit)
+    }
+
+    /// Like [`GitRepository::list_commit`], along with whether more commits
+    /// follow the page.
+    pub(crate) fn list_commit_page(
+        &self,
+        options: ListCommitOptions,
+    ) -> GitResult<(Vec<GitCommit>, bool)> {
+        let limit = options.limit.min(MAX_COMMIT_COUNT);
+        let mut commits = self.walk_commits(options, limit + 1)?;
+        let has_more = commits.len() > limit;
+        commits.truncate(limit);
+        Ok((commits, has_more))
+    }
+
+    fn walk_commits(&self, options: ListCommitOptions, limit: usize) -> GitResult<Vec<GitCommit>> {
+        let _timer = metrics::start_timer(GitOperation::Revwalk);
+        let mailmap = self.mailmap(options.mailmap);
+        let until = options.until;
         let mut revwalk = self.repo.revwalk()?;
-        revwalk.push_head()?;
-        Ok(revwalk
+        match options.start {
+            Some(start) => revwalk.push(start.0)?,
+            None => revwalk.push(self.head_id()?.0)?,
+        }
+        if let Some(exclude) = options.exclude {
+            revwalk.hide(exclude.0)?;
+        }
+        let boundary = self.shallow_boundary();
+        let mut commits = vec![];
+        for (n, id) in revwalk
             .flatten()
-            .filter_map(|id| {
-                self.repo
-                    .find_commit(id)
-                    .map(|c| {
-                        Some(GitCommit {
-                            author: c.author().into(),
-                            committer: c.committer().into(),
-                            id: c.id().into(),
-                            message: c.message_bytes().into(),
-                            short_id: c.get_short_id(),
-                            time: c.time().datetime(),
-                        })
-                    })
-                    .unwrap_or_default()
-            })
-            .collect())
+            .take_while(|id| until.as_ref().is_none_or(|u| u.0.ne(id)))
+            .enumerate()
+        {
+            self.deadline.check()?;
+            if commits.len() >= limit {
+                break;
+            }
+            if n < options.offset {
+                continue;
+            }
+            let Ok(commit) = self.repo.find_commit(id) else {
+                continue;
+            };
+            let mut entry = self.convert_to_commit(&commit, mailmap.as_ref());
+            entry.truncated_history = boundary.contains(&id);
+            if options.with_stats && !entry.truncated_history {
+                entry.stats = self.diff_stats(&commit).ok();
+            }
+            commits.push(entry);
+        }
+        Ok(commits)
     }
 
     pub(crate) fn list_index(&self, path: &str) -> GitResult<Vec<GitIndex>> {
@@ -195,6 +1047,7 @@ impl GitRepository {
         } else {
             path.split('/').count()
         };
+        let abbrev_len = self.abbrev_len();
         let convert_to_index_entry = |entry: IndexEntry, path: MaybeLossyUtf8| GitIndexEntry {
             ctime: entry.ctime.seconds(),
             file_size: entry.file_size,
@@ -205,16 +1058,16 @@ impl GitRepository {
             name: path
                 .0
                 .split('/')
-                .last()
+                .next_back()
                 .map(str::to_string)
                 .unwrap_or_default()
                 .into(),
             path,
-            short_id: self
-                .repo
-                .find_blob(entry.id)
-                .map(|o| o.get_short_id())
-                .unwrap_or_default(),
+            short_id: {
+                let mut id = entry.id.to_string();
+                id.truncate(abbrev_len);
+                id
+            },
             uid: entry.uid,
         };
         let mut set = HashSet::<String>::new();
@@ -252,10 +1105,119 @@ impl GitRepository {
             .collect())
     }
 
+    pub(crate) fn list_license(&self) -> GitResult<Vec<GitLicense>> {
+        let root = self.default_reference()?.peel_to_tree()?;
+        Ok(root
+            .iter()
+            .filter(|e| matches!(e.kind(), Some(ObjectType::Blob)))
+            .filter(|e| license::is_license_file(&String::from_utf8_lossy(e.name_bytes())))
+            .filter_map(|e| {
+                let blob = self.repo.find_blob(e.id()).ok()?;
+                let (spdx_id, name) =
+                    license::detect_license(&String::from_utf8_lossy(blob.content()))?;
+                Some(GitLicense {
+                    id: e.id().into(),
+                    name: name.into(),
+                    path: e.name_bytes().into(),
+                    spdx_id: spdx_id.into(),
+                })
+            })
+            .collect())
+    }
+
+    pub(crate) fn list_path(&self, id: &GitOid) -> GitResult<Vec<String>> {
+        let _timer = metrics::start_timer(GitOperation::TreeWalk);
+        let tree = self.repo.find_commit(id.0)?.tree()?;
+        let mut vec = vec![];
+        let result = tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+            if self.deadline.is_expired() {
+                return TreeWalkResult::Abort;
+            }
+            if let Some(ObjectType::Blob) = entry.kind() {
+                vec.push(format!(
+                    "{root}{}",
+                    String::from_utf8_lossy(entry.name_bytes())
+                ));
+            }
+            TreeWalkResult::Ok
+        });
+        self.deadline.check()?;
+        result?;
+        Ok(vec)
+    }
+
+    /// Entries of the tree at `reference`, or with `recursive` every file
+    /// below it without the intermediate trees, like `git ls-tree -r`.
+    /// At most `MAX_MANIFEST_ENTRIES` are returned per page.
+    pub(crate) fn list_manifest(
+        &self,
+        reference: &str,
+        recursive: bool,
+        offset: usize,
+        limit: usize,
+    ) -> GitResult<GitManifest> {
+        let _timer = metrics::start_timer(GitOperation::TreeWalk);
+        let tree = self.repo.revparse_single(reference)?.peel_to_tree()?;
+        let odb = self.repo.odb()?;
+        let limit = limit.clamp(1, MAX_MANIFEST_ENTRIES);
+        let mut entries = vec![];
+        let mut total = 0;
+        let result = tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+            if self.deadline.is_expired() {
+                return TreeWalkResult::Abort;
+            }
+            let is_tree = entry.kind() == Some(ObjectType::Tree);
+            if !recursive || !is_tree {
+                if (offset..offset.saturating_add(limit)).contains(&total) {
+                    let mut path = root.as_bytes().to_vec();
+                    path.extend_from_slice(entry.name_bytes());
+                    entries.push(GitManifestEntry {
+                        filemode: entry.filemode(),
+                        id: entry.id().into(),
+                        kind: entry.kind().map(Into::into),
+                        path: path[..].into(),
+                        size: match entry.kind() {
+                            Some(ObjectType::Blob) => {
+                                odb.read_header(entry.id()).ok().map(|(s, _)| s)
+                            }
+                            _ => None,
+                        },
+                    });
+                }
+                total += 1;
+            }
+            if is_tree && !recursive {
+                TreeWalkResult::Skip
+            } else {
+                TreeWalkResult::Ok
+            }
+        });
+        self.deadline.check()?;
+        result?;
+        let end = offset.saturating_add(limit);
+        Ok(GitManifest {
+            entries,
+            next_cursor: (end < total).then_some(end),
+            total,
+        })
+    }
+
     pub(crate) fn list_reference(&self) -> GitResult<Vec<GitReference>> {
-        Ok(self
-            .repo
-            .references()?
+        self.list_reference_matching(&ListReferenceOptions::default())
+    }
+
+    pub(crate) fn list_reference_matching(
+        &self,
+        opts: &ListReferenceOptions,
+    ) -> GitResult<Vec<GitReference>> {
+        let prefix = opts.namespace.map_or("refs/*/", |n| n.prefix());
+        let references = match (opts.namespace, opts.pattern.as_deref()) {
+            (None, None) => self.repo.references()?,
+            (_, pattern) => self
+                .repo
+                .references_glob(&format!("{prefix}{}", pattern.unwrap_or("*")))?,
+        };
+        Ok(references
             .flatten()
             .map(|r| GitReference {
                 kind: r.kind().map(Into::into),
@@ -267,12 +1229,26 @@ impl GitRepository {
             .collect())
     }
 
+    pub(crate) fn list_reference_at(&self, id: &GitOid) -> GitResult<Vec<GitReference>> {
+        Ok(self
+            .list_reference()?
+            .into_iter()
+            .filter(|r| {
+                self.repo
+                    .find_reference(&r.name.0)
+                    .and_then(|r| r.peel_to_commit())
+                    .is_ok_and(|c| c.id() == id.0)
+            })
+            .collect())
+    }
+
     pub(crate) fn list_remote(&self) -> GitResult<Vec<GitRemote>> {
         Ok(self
             .repo
             .remotes()?
             .iter_bytes()
             .map(|r| GitRemote {
+                fetched_at: self.fetched_at(&String::from_utf8_lossy(r)),
                 name: r.into(),
                 url: self
                     .repo
@@ -283,13 +1259,73 @@ impl GitRepository {
             .collect())
     }
 
+    /// Like `git ls-remote --heads --tags`, comparing each advertised ref
+    /// with the tracking ref the remote's fetch refspecs map it to.
+    pub(crate) fn list_remote_ref(&self, name: &str) -> GitResult<Vec<GitRemoteRef>> {
+        let mut remote = self.repo.find_remote(name)?;
+        remote.connect(Direction::Fetch)?;
+        let refspecs = remote.refspecs().collect::<Vec<_>>();
+        let mut refs = vec![];
+        for head in remote.list()? {
+            let name = head.name();
+            if !name.starts_with("refs/heads/") && !name.starts_with("refs/tags/") {
+                continue;
+            }
+            let tracking = refspecs
+                .iter()
+                .filter(|r| r.direction() == Direction::Fetch && r.src_matches(name))
+                .find_map(|r| r.transform(name).ok())
+                .and_then(|dst| self.repo.refname_to_id(dst.as_str()?).ok());
+            let status = match tracking {
+                Some(id) if id == head.oid() => GitRemoteRefStatus::UpToDate,
+                Some(_) => GitRemoteRefStatus::Stale,
+                None => GitRemoteRefStatus::Missing,
+            };
+            refs.push(GitRemoteRef {
+                name: name.as_bytes().into(),
+                status,
+                target: head.oid().into(),
+                tracking: tracking.map(Into::into),
+            });
+        }
+        Ok(refs)
+    }
+
     pub(crate) fn list_tag(&self) -> GitResult<Vec<GitTag>> {
+        self.list_tag_matching(None)
+    }
+
+    /// `pattern` is a glob matched against the tag name, e.g. `v1.*`.
+    pub(crate) fn list_tag_matching(&self, pattern: Option<&str>) -> GitResult<Vec<GitTag>> {
+        let matches = match pattern {
+            Some(pattern) => Some(
+                self.repo
+                    .references_glob(&format!("refs/tags/{pattern}"))?
+                    .flatten()
+                    .map(|r| r.name_bytes().to_vec())
+                    .collect::<HashSet<_>>(),
+            ),
+            None => None,
+        };
         let mut vec = vec![];
         self.repo.tag_foreach(|id, name| {
+            if matches.as_ref().is_some_and(|m| !m.contains(name)) {
+                return true;
+            }
             if let Ok(r) = self.repo.find_reference(&String::from_utf8_lossy(name)) {
+                let tag = self.repo.find_tag(id).ok();
+                let commit = r.peel_to_commit().ok();
+                let tagger = tag.as_ref().and_then(|t| t.tagger());
                 vec.push(GitTag {
+                    commit: commit.as_ref().map(|c| c.id().into()),
+                    message: tag.as_ref().and_then(|t| t.message_bytes()).map(Into::into),
                     name: name.into(),
                     shorthand: r.shorthand_bytes().into(),
+                    time: tagger
+                        .as_ref()
+                        .map(|s| s.when().datetime())
+                        .or_else(|| commit.as_ref().map(|c| c.time().datetime())),
+                    tagger: tagger.map(|s| self.convert_to_signature(&s, None)),
                     target: id.into(),
                     target_short: r.get_short_id(),
                 });
@@ -299,10 +1335,43 @@ impl GitRepository {
         Ok(vec)
     }
 
+    pub(crate) fn list_tag_containing(&self, id: GitOid) -> GitResult<GitCommitTags> {
+        self.repo.find_commit(id.0)?;
+        let mut tags = vec![];
+        for tag in self.list_tag()? {
+            self.deadline.check()?;
+            let target = match self
+                .repo
+                .find_reference(&tag.name.0)
+                .and_then(|r| r.peel_to_commit())
+            {
+                Ok(commit) => commit.id(),
+                Err(_) => continue,
+            };
+            if self.contains_commit(target, id.0) {
+                tags.push((self.count_between(id.0, target)?, tag));
+            }
+        }
+        let nearest = tags
+            .iter()
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, tag)| tag.clone());
+        Ok(GitCommitTags {
+            nearest,
+            tags: tags.into_iter().map(|(_, tag)| tag).collect(),
+        })
+    }
+
     pub(crate) fn list_tree(&self, path: &str) -> GitResult<Vec<GitTree>> {
+        self.list_tree_at(&self.head_id()?, path)
+    }
+
+    pub(crate) fn list_tree_at(&self, id: &GitOid, path: &str) -> GitResult<Vec<GitTree>> {
         let path = path.strip_suffix('/').unwrap_or(path);
-        let commit = self.repo.head()?.peel_to_commit()?;
+        let commit = self.repo.find_commit(id.0)?;
         let root = commit.tree()?;
+        let odb = self.repo.odb()?;
+        let abbrev_len = self.abbrev_len();
         let convert_to_tree = |entry: &TreeEntry<'_>, root: &str| -> GitTree {
             GitTree {
                 filemode: entry.filemode(),
@@ -313,7 +1382,16 @@ impl GitRepository {
                 short_id: entry
                     .to_object(&self.repo)
                     .map(|o| o.get_short_id())
-                    .unwrap_or_default(),
+                    .unwrap_or_else(|_| {
+                        let mut id = entry.id().to_string();
+                        id.truncate(abbrev_len);
+                        id
+                    }),
+                size: match entry.kind() {
+                    Some(ObjectType::Blob) => odb.read_header(entry.id()).ok().map(|(s, _)| s),
+                    _ => None,
+                },
+                submodule_url: None,
             }
         };
         let collect_tree = |tree: Tree<'_>, root: &str| -> Vec<_> {
@@ -322,7 +1400,8 @@ impl GitRepository {
                 .collect()
         };
         if path.is_empty() {
-            let vec = collect_tree(root, "");
+            let mut vec = collect_tree(root.clone(), "");
+            self.add_submodule_urls(&root, &mut vec)?;
             return Ok(vec);
         }
         let mut vec = vec![];
@@ -346,15 +1425,47 @@ impl GitRepository {
             TreeWalkResult::Ok
         })
         .unwrap_or_default();
+        self.add_submodule_urls(&root, &mut vec)?;
         Ok(vec)
     }
 
-    pub(crate) fn open<P>(path: P) -> GitResult<GitRepository>
+    pub(crate) fn list_unreleased(
+        &self,
+        mut options: ListCommitOptions,
+    ) -> GitResult<GitUnreleased> {
+        let start = match options.start.take() {
+            Some(start) => start,
+            None => self.head_id()?,
+        };
+        let tag = self.describe_tag(&start)?;
+        options.exclude = match &tag {
+            Some(tag) => Some(self.resolve_commit(tag)?),
+            None => None,
+        };
+        options.start = Some(start);
+        Ok(GitUnreleased {
+            commits: self.list_commit(options)?,
+            tag,
+        })
+    }
+
+    pub(crate) fn merge_base(&self, base: &str, head: &str) -> GitResult<GitOid> {
+        let base = self.resolve_commit(base)?;
+        let head = self.resolve_commit(head)?;
+        Ok(self.repo.merge_base(base.0, head.0)?.into())
+    }
+
+    fn mailmap(&self, enabled: bool) -> Option<Mailmap> {
+        enabled.then(|| self.repo.mailmap().ok()).flatten()
+    }
+
+    pub(crate) fn open<P>(path: P) -> GitResult<GitRepository>
     where
         P: AsRef<Path>,
     {
+        let _timer = metrics::start_timer(GitOperation::Open);
         Repository::open(&path)
-            .map(|r| GitRepository { repo: r })
+            .map(GitRepository::from)
             .map_err(|e| match (e.class(), e.code()) {
                 (ErrorClass::Os | ErrorClass::Repository, ErrorCode::NotFound) => {
                     GitError::RepositoryNotFound(path.as_ref().into())
@@ -362,23 +1473,288 @@ impl GitRepository {
                 _ => e.into(),
             })
     }
+
+    fn reflog_time(&self, name: &str) -> Option<OffsetDateTime> {
+        let reflog = self.repo.reflog(name).ok()?;
+        let time = reflog.get(0)?.committer().when().datetime();
+        Some(time)
+    }
+
+    /// The tree `spec` resolves to, or the object itself when it is not
+    /// tree-ish, e.g. a blob named by `ref:path`.
+    pub(crate) fn resolve_tree(&self, spec: &str) -> GitResult<GitOid> {
+        let object = self.repo.revparse_single(spec)?;
+        Ok(object.peel_to_tree().map_or(object.id(), |t| t.id()).into())
+    }
+
+    pub(crate) fn resolve_commit(&self, spec: &str) -> GitResult<GitOid> {
+        Ok(self
+            .repo
+            .revparse_single(spec)?
+            .peel_to_commit()?
+            .id()
+            .into())
+    }
+
+    /// Commits on `HEAD` touching `path`, or changing the number of
+    /// occurrences of `pickaxe` like `git log -S`. Since the pickaxe reads
+    /// every blob changed by each commit, it only looks at the most recent
+    /// `MAX_PICKAXE_COMMITS`.
+    pub(crate) fn search_commit(&self, options: SearchCommitOptions) -> GitResult<Vec<GitCommit>> {
+        let _timer = metrics::start_timer(GitOperation::Revwalk);
+        let pickaxe = options.pickaxe.as_deref().filter(|p| !p.is_empty());
+        let mut path = options.path.filter(|p| !p.is_empty());
+        if pickaxe.is_none() && path.is_none() {
+            return Ok(vec![]);
+        }
+        let mailmap = self.mailmap(options.mailmap);
+        let boundary = self.shallow_boundary();
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push(self.head_id()?.0)?;
+        let mut commits = vec![];
+        let limit = match pickaxe {
+            Some(_) => MAX_PICKAXE_COMMITS,
+            None => usize::MAX,
+        };
+        for id in revwalk.flatten().take(limit) {
+            self.deadline.check()?;
+            // Diffing against the missing parents would report every file as
+            // added, so the boundary is left out of the search.
+            if boundary.contains(&id) {
+                continue;
+            }
+            let commit = self.repo.find_commit(id)?;
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            let mut diff = match (&path, options.follow) {
+                (Some(path), false) => {
+                    let mut opts = git2::DiffOptions::new();
+                    opts.pathspec(path).disable_pathspec_match(true);
+                    self.diff_to_parent(&commit, Some(&mut opts))?
+                }
+                _ => self.diff_to_parent(&commit, None)?,
+            };
+            if options.follow && path.is_some() {
+                diff.find_similar(None)?;
+            }
+            let mut renamed_from = None;
+            let changed = diff.deltas().any(|d| {
+                if let Some(path) = &path {
+                    if d.new_file().path() != Some(Path::new(path)) {
+                        return false;
+                    }
+                    if d.status() == Delta::Renamed {
+                        renamed_from = d.old_file().path().map(|p| p.to_string_lossy().into());
+                    }
+                }
+                pickaxe.is_none_or(|pickaxe| {
+                    !self.deadline.is_expired()
+                        && self.count_occurrences(d.old_file().id(), pickaxe.as_bytes())
+                            != self.count_occurrences(d.new_file().id(), pickaxe.as_bytes())
+                })
+            });
+            self.deadline.check()?;
+            if renamed_from.is_some() {
+                path = renamed_from;
+            }
+            if changed {
+                commits.push(self.convert_to_commit(&commit, mailmap.as_ref()));
+                if commits.len() >= MAX_COMMIT_COUNT {
+                    break;
+                }
+            }
+        }
+        Ok(commits)
+    }
+
+    /// Commits whose parents were cut off by a shallow clone. libgit2 walks
+    /// them as root commits.
+    fn shallow_boundary(&self) -> HashSet<Oid> {
+        if !self.repo.is_shallow() {
+            return HashSet::new();
+        }
+        std::fs::read_to_string(self.repo.commondir().join("shallow"))
+            .map(|s| {
+                s.lines()
+                    .filter_map(|l| Oid::from_str(l.trim()).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn split_reference<'a>(&self, spec: &'a str) -> Option<(&'a str, &'a str)> {
+        let is_reference = |name: &str| {
+            self.repo
+                .resolve_reference_from_short_name(name)
+                .is_ok_and(|r| r.peel_to_commit().is_ok())
+        };
+        let is_commit = |name: &str| {
+            (7..=40).contains(&name.len())
+                && name.bytes().all(|b| b.is_ascii_hexdigit())
+                && self.resolve_commit(name).is_ok()
+        };
+        spec.match_indices('/')
+            .map(|(i, _)| i)
+            .chain(std::iter::once(spec.len()))
+            .rev()
+            .map(|i| (&spec[..i], spec[i..].trim_start_matches('/')))
+            .find(|&(name, _)| !name.is_empty() && (is_reference(name) || is_commit(name)))
+    }
+
+    pub(crate) fn summarize(&self, max_size: usize) -> GitResult<GitSummary> {
+        let commit = self.default_reference()?.peel_to_commit()?;
+        let entries = self.list_tree("")?;
+        let readme = entries
+            .iter()
+            .filter(|e| matches!(e.kind, Some(GitObjectType::Blob)))
+            .find(|e| {
+                let name = e.name.0.to_ascii_lowercase();
+                name == "readme" || name.starts_with("readme.")
+            })
+            .map(|e| -> GitResult<GitReadme> {
+                let blob = self.repo.find_blob(e.id.0)?;
+                let (text, _) = encoding::decode(blob.content());
+                Ok(GitReadme {
+                    content: encoding::truncate(text.as_bytes(), max_size).into(),
+                    name: e.name.clone(),
+                })
+            })
+            .transpose()?;
+        let mut head = self.convert_to_commit(&commit, self.mailmap(true).as_ref());
+        head.truncated_history = self.shallow_boundary().contains(&commit.id());
+        if !head.truncated_history {
+            head.stats = Some(self.diff_stats(&commit)?);
+        }
+        Ok(GitSummary {
+            entries,
+            head,
+            partial_clone: self.is_partial_clone(),
+            readme,
+            shallow: self.repo.is_shallow(),
+            state: self.state(),
+            stats: self.gather_tree_stats()?,
+            worktree: self.worktree()?,
+        })
+    }
+
+    pub(crate) fn walk_text_blobs<F>(&self, id: &GitOid, max_size: usize, mut f: F) -> GitResult<()>
+    where
+        F: FnMut(&str, &str) -> bool,
+    {
+        let _timer = metrics::start_timer(GitOperation::TreeWalk);
+        let tree = self.repo.find_commit(id.0)?.tree()?;
+        let odb = self.repo.odb()?;
+        let attributes = TreeAttributes::new(&self.repo, &tree)?;
+        let mut aborted = false;
+        let result = tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+            if self.deadline.is_expired() {
+                return TreeWalkResult::Abort;
+            }
+            if let Some(ObjectType::Blob) = entry.kind() {
+                if odb
+                    .read_header(entry.id())
+                    .is_ok_and(|(size, _)| size <= max_size)
+                {
+                    if let Ok(blob) = self.repo.find_blob(entry.id()) {
+                        let path = format!("{root}{}", String::from_utf8_lossy(entry.name_bytes()));
+                        let is_binary =
+                            attributes.binary(&path).unwrap_or_else(|| blob.is_binary());
+                        let excluded = attributes.linguist(&path).excluded;
+                        if !is_binary
+                            && !excluded
+                            && !f(&path, &String::from_utf8_lossy(blob.content()))
+                        {
+                            aborted = true;
+                            return TreeWalkResult::Abort;
+                        }
+                    }
+                }
+            }
+            TreeWalkResult::Ok
+        });
+        self.deadline.check()?;
+        match result {
+            Err(_) if aborted => Ok(()),
+            r => Ok(r?),
+        }
+    }
+
+    /// Describes the linked worktree being viewed, when WIT_REPO_ROOT points
+    /// at one rather than at the main checkout.
+    pub(crate) fn worktree(&self) -> GitResult<Option<GitWorktree>> {
+        if !self.repo.is_worktree() {
+            return Ok(None);
+        }
+        let worktree = Worktree::open_from_repository(&self.repo)?;
+        let branch = self
+            .repo
+            .head()
+            .ok()
+            .filter(Reference::is_branch)
+            .map(|r| String::from_utf8_lossy(r.shorthand_bytes()).into_owned());
+        Ok(Some(GitWorktree {
+            branch,
+            name: worktree.name().map(str::to_string),
+            path: worktree.path().to_string_lossy().into_owned(),
+        }))
+    }
+
+    /// The merge, rebase or similar operation left in progress, if any.
+    pub(crate) fn state(&self) -> GitRepositoryState {
+        self.repo.state().into()
+    }
+
+    pub(crate) fn with_deadline(mut self, deadline: Deadline) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    pub(crate) fn with_settings(mut self, settings: Arc<GitSettings>) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    fn convert_to_commit(&self, c: &Commit<'_>, mailmap: Option<&Mailmap>) -> GitCommit {
+        GitCommit {
+            author: self.convert_to_signature(&c.author(), mailmap),
+            committer: self.convert_to_signature(&c.committer(), mailmap),
+            id: c.id().into(),
+            is_merge: c.parent_count() > 1,
+            message: c.message_bytes().into(),
+            parent_count: c.parent_count(),
+            parents: c.parent_ids().map(Into::into).collect(),
+            short_id: c.get_short_id(),
+            stats: None,
+            time: c.time().datetime(),
+            truncated_history: false,
+        }
+    }
+
+    fn convert_to_signature(&self, sig: &Signature<'_>, mailmap: Option<&Mailmap>) -> GitSignature {
+        let template = self.settings.avatar_url_template.as_deref();
+        match mailmap.map(|m| m.resolve_signature(sig)) {
+            Some(Ok(resolved)) => GitSignature::new(&resolved, template),
+            _ => GitSignature::new(sig, template),
+        }
+    }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use std::{
-        fs::{create_dir_all, File},
-        io::Write,
+        fs::{create_dir_all, rename, File},
+        io::{Read, Write},
     };
 
     use git2::{Signature, Status};
     use tempfile::tempdir;
 
-    use model::GitBranchType;
+    use model::{GitBranchType, GitObjectType};
 
     use super::*;
 
-    fn commit_with_signature(
+    pub(crate) fn commit_with_signature(
         repo: &Repository,
         tree_id: Oid,
         message: &str,
@@ -394,11 +1770,19 @@ mod tests {
             None => Signature::now(name, email),
         }
         .unwrap_or_else(|e| panic!("create git signature failed: {e:?}"));
-        repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &[])
-            .unwrap_or_else(|e| panic!("create git commit failed: {e:?}"))
+        let parent = repo.head().and_then(|h| h.peel_to_commit()).ok();
+        repo.commit(
+            Some("HEAD"),
+            &sig,
+            &sig,
+            message,
+            &tree,
+            parent.as_ref().into_iter().collect::<Vec<_>>().as_slice(),
+        )
+        .unwrap_or_else(|e| panic!("create git commit failed: {e:?}"))
     }
 
-    fn create_file_with_content<P: AsRef<Path>>(file_path: P, content: &str) {
+    pub(crate) fn create_file_with_content<P: AsRef<Path>>(file_path: P, content: &str) {
         if let Some(parent) = file_path.as_ref().parent() {
             create_dir_all(parent).unwrap_or_else(|e| panic!("create parent dir failed: {e:?}"));
         }
@@ -415,16 +1799,16 @@ mod tests {
             .unwrap_or_else(|e| panic!("create git tag failed: {e:?}"))
     }
 
-    fn initialize_git_repo<P: AsRef<Path>>(path: P) -> Repository {
+    pub(crate) fn initialize_git_repo<P: AsRef<Path>>(path: P) -> Repository {
         Repository::init(path).unwrap_or_else(|e| panic!("initialize git repo failed: {e:?}"))
     }
 
-    fn set_git_head_to_branch(repo: &Repository, branch: &str) {
+    pub(crate) fn set_git_head_to_branch(repo: &Repository, branch: &str) {
         repo.set_head(&format!("refs/heads/{branch}"))
             .unwrap_or_else(|e| panic!("set git head failed: {e:?}"));
     }
 
-    fn write_index_tree(repo: &Repository, index_entry: &[&Path]) -> Oid {
+    pub(crate) fn write_index_tree(repo: &Repository, index_entry: &[&Path]) -> Oid {
         let mut index = repo
             .index()
             .unwrap_or_else(|e| panic!("get git index failed: {e:?}"));
@@ -438,6 +1822,306 @@ mod tests {
             .unwrap_or_else(|e| panic!("write git index failed: {e:?}"))
     }
 
+    #[test]
+    fn test_apply_patch() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        create_file_with_content(path.join("README.md"), "wit\n");
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        let first =
+            commit_with_signature(&repo, tree_id, "Initial", "wit", "wit@example.com", None);
+        create_file_with_content(path.join("README.md"), "wit 2\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        let second = commit_with_signature(
+            &repo,
+            tree_id,
+            "Update README\n\nMention the version.",
+            "Jane Doe",
+            "jane@example.com",
+            Some(1727776800),
+        );
+        let mbox = repo
+            .find_commit(second)
+            .and_then(|c| git2::Email::from_commit(&c, &mut git2::EmailCreateOptions::new()))
+            .map(|e| String::from_utf8_lossy(e.as_slice()).into_owned())
+            .unwrap_or_else(|e| panic!("format patch failed: {e:?}"));
+        repo.find_commit(first)
+            .and_then(|c| repo.branch("topic", &c, false))
+            .unwrap_or_else(|e| panic!("create branch failed: {e:?}"));
+
+        let repo: GitRepository = repo.into();
+        let commits = repo
+            .apply_patch("topic", &mbox)
+            .unwrap_or_else(|e| panic!("apply_patch in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(commits.len(), 1);
+        assert_eq!(
+            commits[0].message.to_string(),
+            "Update README\n\nMention the version.\n"
+        );
+        let tip = repo
+            .repo
+            .revparse_single("topic^{tree}")
+            .unwrap_or_else(|e| panic!("resolve topic failed: {e:?}"));
+        assert_eq!(tip.id(), tree_id);
+        let author = repo
+            .repo
+            .revparse_single("topic")
+            .and_then(|o| o.peel_to_commit())
+            .map(|c| (c.author().to_string(), c.author().when().seconds()))
+            .unwrap_or_else(|e| panic!("resolve topic failed: {e:?}"));
+        assert_eq!(
+            author,
+            (String::from("Jane Doe <jane@example.com>"), 1727776800)
+        );
+
+        assert!(matches!(
+            repo.apply_patch("topic", &mbox),
+            Err(GitError::Conflict(_))
+        ));
+        assert!(matches!(
+            repo.apply_patch("main", &mbox),
+            Err(GitError::Conflict(m)) if m.contains("checked out")
+        ));
+        assert!(matches!(
+            repo.apply_patch("topic", "not a patch"),
+            Err(GitError::InvalidPatch(_))
+        ));
+    }
+
+    #[test]
+    fn test_archive() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        create_file_with_content(path.join("src/main.rs"), "fn main() {}\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("src/main.rs")]);
+        commit_with_signature(&repo, tree_id, "Initial", "wit", "wit@example.com", None);
+
+        let repo: GitRepository = repo.into();
+        let archive = repo
+            .archive("main", "", "wit-main/")
+            .unwrap_or_else(|e| panic!("archive in git repo {path:?} should not fail: {e:?}"));
+        let mut tar = vec![];
+        flate2::read::GzDecoder::new(archive.as_slice())
+            .read_to_end(&mut tar)
+            .unwrap_or_else(|e| panic!("decompress archive failed: {e:?}"));
+        assert_eq!(tar.len() % 512, 0);
+        assert!(tar.starts_with(b"wit-main/src/"));
+        assert_eq!(&tar[512..532], b"wit-main/src/main.rs");
+        assert_eq!(&tar[1024..1037], b"fn main() {}\n");
+
+        let archive = repo
+            .archive("main", "src", "wit-main-src/")
+            .unwrap_or_else(|e| panic!("archive in git repo {path:?} should not fail: {e:?}"));
+        let mut tar = vec![];
+        flate2::read::GzDecoder::new(archive.as_slice())
+            .read_to_end(&mut tar)
+            .unwrap_or_else(|e| panic!("decompress archive failed: {e:?}"));
+        assert_eq!(&tar[..20], b"wit-main-src/main.rs");
+
+        repo.archive("missing", "", "wit/")
+            .expect_err("archive of a missing reference should fail");
+        repo.archive("main", "src/main.rs", "wit/")
+            .expect_err("archive of a file should fail");
+
+        let repo = repo.with_settings(Arc::new(GitSettings {
+            memory_budget: 8,
+            ..Default::default()
+        }));
+        let result = repo.archive("main", "", "wit/");
+        assert!(matches!(result, Err(GitError::TooLarge(_))), "{result:?}");
+    }
+
+    #[test]
+    fn test_blame_file() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+        set_git_head_to_branch(&repo, "main");
+
+        let file_path = Path::new("README.md");
+        let commit_ids = ["git\n", "git\nweb\nwit\n"].map(|content| {
+            create_file_with_content(path.join(file_path), content);
+            let tree_id = write_index_tree(&repo, &[file_path]);
+            commit_with_signature(&repo, tree_id, content, "wit", "wit@example.com", None)
+        });
+
+        let repo: GitRepository = repo.into();
+        let hunks = repo
+            .blame_file(&commit_ids[1].into(), "README.md", true)
+            .unwrap_or_else(|e| panic!("blame_file in git repo {path:?} should not fail: {e:?}"));
+        let summary = hunks
+            .iter()
+            .map(|h| (h.commit_id.0, h.start_line, h.line_count))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, [(commit_ids[0], 1, 1), (commit_ids[1], 2, 2)]);
+        assert_eq!(hunks[0].author.name.to_string(), "wit");
+
+        let hunks = repo
+            .blame_file(&commit_ids[0].into(), "README.md", true)
+            .unwrap_or_else(|e| panic!("blame_file in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(hunks.len(), 1);
+        assert_eq!(hunks[0].line_count, 1);
+    }
+
+    #[test]
+    fn test_branch_divergence() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let base_id = commit_with_signature(&repo, tree_id, "Base", "wit", "wit@example.com", None);
+        let base = repo
+            .find_commit(base_id)
+            .unwrap_or_else(|e| panic!("find git commit failed: {e:?}"));
+        repo.branch("feature", &base, false)
+            .unwrap_or_else(|e| panic!("create git branch failed: {e:?}"));
+        drop(base);
+        commit_with_signature(&repo, tree_id, "Next", "wit", "wit@example.com", None);
+
+        let repo: GitRepository = repo.into();
+        let divergence = repo.branch_divergence("feature").unwrap_or_else(|e| {
+            panic!("branch_divergence in git repo {path:?} should not fail: {e:?}")
+        });
+        assert_eq!(divergence.shorthand.to_string(), "feature");
+        assert!(divergence.upstream.is_none());
+        let default_branch = divergence
+            .default_branch
+            .unwrap_or_else(|| panic!("default branch should be reported"));
+        assert_eq!(default_branch.shorthand.to_string(), "main");
+        assert_eq!((default_branch.ahead, default_branch.behind), (0, 1));
+
+        assert!(repo.branch_divergence("missing").is_err());
+    }
+
+    #[test]
+    fn test_default_branch() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let base_id = commit_with_signature(&repo, tree_id, "Base", "wit", "wit@example.com", None);
+        let base = repo
+            .find_commit(base_id)
+            .unwrap_or_else(|e| panic!("find git commit failed: {e:?}"));
+        for name in ["release", "stable"] {
+            repo.branch(name, &base, false)
+                .unwrap_or_else(|e| panic!("create git branch failed: {e:?}"));
+        }
+        drop(base);
+        let head_id = commit_with_signature(&repo, tree_id, "Next", "wit", "wit@example.com", None);
+
+        let assert_default = |repo: &GitRepository, name: &str, overridden: bool| {
+            let default = repo.default_branch().unwrap_or_else(|e| {
+                panic!("default_branch in git repo {path:?} should not fail: {e:?}")
+            });
+            assert_eq!(
+                (default.name.as_str(), default.overridden),
+                (name, overridden)
+            );
+        };
+        let repo: GitRepository = repo.into();
+        assert_default(&repo, "main", false);
+        assert_eq!(repo.head_id().map(|id| id.0).ok(), Some(head_id));
+
+        let repo = repo.with_settings(Arc::new(GitSettings {
+            default_branch: Some(String::from("stable")),
+            ..Default::default()
+        }));
+        assert_default(&repo, "stable", true);
+        assert_eq!(repo.head_id().map(|id| id.0).ok(), Some(base_id));
+
+        assert!(repo
+            .override_default_branch(Some(String::from("missing")))
+            .is_err());
+        repo.override_default_branch(Some(String::from("release")))
+            .unwrap_or_else(|e| panic!("override_default_branch should not fail: {e:?}"));
+        assert_default(&repo, "release", true);
+        let reopened = GitRepository::open(path)
+            .unwrap_or_else(|e| panic!("open git repo {path:?} failed: {e:?}"));
+        assert_default(&reopened, "release", true);
+
+        repo.override_default_branch(None)
+            .unwrap_or_else(|e| panic!("override_default_branch should not fail: {e:?}"));
+        assert_default(&repo, "stable", true);
+        assert_default(&reopened, "main", false);
+    }
+
+    #[test]
+    fn test_diff_commit() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        create_file_with_content(path.join("main.rs"), "fn main() {\n}\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("main.rs")]);
+        let base_id = commit_with_signature(&repo, tree_id, "Base", "wit", "wit@example.com", None);
+        create_file_with_content(path.join("main.rs"), "fn  main() {\n}   \n");
+        let tree_id = write_index_tree(&repo, &[Path::new("main.rs")]);
+        let head_id =
+            commit_with_signature(&repo, tree_id, "Reformat", "wit", "wit@example.com", None);
+
+        let repo: GitRepository = repo.into();
+        let files = repo
+            .diff_commit(base_id.into(), Default::default())
+            .unwrap_or_else(|e| panic!("diff_commit in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(files.len(), 1);
+        assert!(files[0].old_path.is_none());
+        assert_eq!(files[0].hunks[0].lines.len(), 2);
+
+        let files = repo
+            .diff_commit(head_id.into(), Default::default())
+            .unwrap_or_else(|e| panic!("diff_commit in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(files[0].hunks.len(), 1);
+        let lines = &files[0].hunks[0].lines;
+        assert_eq!(
+            lines
+                .iter()
+                .filter(|l| l.origin == model::GitDiffLineType::Addition)
+                .count(),
+            2
+        );
+
+        let sample = [
+            (IgnoreWhitespace::All, 0),
+            (IgnoreWhitespace::Change, 0),
+            (IgnoreWhitespace::Eol, 1),
+        ];
+        for (ignore_whitespace, hunk_count) in sample.into_iter() {
+            let options = DiffOptions {
+                ignore_whitespace: Some(ignore_whitespace),
+                ..Default::default()
+            };
+            let files = repo
+                .compare(&base_id.to_string(), "HEAD", options)
+                .unwrap_or_else(|e| panic!("compare in git repo {path:?} should not fail: {e:?}"));
+            assert_eq!(
+                files.iter().map(|f| f.hunks.len()).sum::<usize>(),
+                hunk_count,
+                "unexpected hunks ignoring whitespace {ignore_whitespace:?}"
+            );
+        }
+
+        let repo = repo.with_settings(Arc::new(GitSettings {
+            memory_budget: 20,
+            ..Default::default()
+        }));
+        let result = repo.diff_commit(head_id.into(), Default::default());
+        assert!(matches!(result, Err(GitError::TooLarge(_))), "{result:?}");
+        let result = repo.compare(&base_id.to_string(), "HEAD", Default::default());
+        assert!(matches!(result, Err(GitError::TooLarge(_))), "{result:?}");
+    }
+
     #[test]
     fn test_gather_status() {
         let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
@@ -457,6 +2141,250 @@ mod tests {
         assert_eq!(item.path.to_string(), file_name);
         assert_eq!(item.status.0, Status::WT_NEW);
         assert_eq!(item.status_bits, 128);
+
+        assert_eq!(repo.state(), GitRepositoryState::Clean);
+        create_file_with_content(path.join(".git/MERGE_HEAD"), &format!("{}\n", Oid::zero()));
+        assert_eq!(repo.state(), GitRepositoryState::Merge);
+        assert_eq!(repo.state().operation(), Some("merge"));
+    }
+
+    #[test]
+    fn test_gather_tree_stats() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        let file_list = [
+            ("README.md", "git + web = wit\n"),
+            ("LICENSE", "BSD-3-Clause\n"),
+            ("src/main.rs", "fn main() {}\n"),
+            ("src/lib/mod.rs", "mod lib;\n"),
+            ("docs/guide.md", "# Guide\n"),
+            ("assets/site.css", "body {}\n"),
+            ("assets/vendor/lib.css", "body {}\n"),
+            ("include/wit.h", "int wit;\n"),
+            (
+                ".gitattributes",
+                "**/vendor/** linguist-vendored\n*.h linguist-language=C++\n",
+            ),
+        ];
+        for (file_name, content) in file_list.iter() {
+            create_file_with_content(path.join(file_name), content);
+        }
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &file_list.map(|(f, _)| Path::new(f)));
+        commit_with_signature(
+            &repo,
+            tree_id,
+            "Initial commit",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+
+        let repo: GitRepository = repo.into();
+        let stats = repo.gather_tree_stats().unwrap_or_else(|e| {
+            panic!("gather_tree_stats in git repo {path:?} should not fail: {e:?}")
+        });
+
+        assert_eq!(stats.file_count, file_list.len());
+        assert_eq!(
+            stats.size,
+            file_list.iter().map(|(_, c)| c.len() as u64).sum::<u64>()
+        );
+        let languages = stats
+            .languages
+            .iter()
+            .map(|l| (l.name.as_str(), l.file_count))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            languages,
+            [("Markdown", 2), ("Rust", 2), ("C++", 1), ("CSS", 1)]
+        );
+        assert_eq!(stats.license, None);
+    }
+
+    #[test]
+    fn test_get_commit() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let base_id = commit_with_signature(&repo, tree_id, "Base", "wit", "wit@example.com", None);
+        let tree = repo
+            .find_tree(tree_id)
+            .unwrap_or_else(|e| panic!("find git tree failed: {e:?}"));
+        let sig = Signature::now("wit", "wit@example.com")
+            .unwrap_or_else(|e| panic!("create git signature failed: {e:?}"));
+        let base = repo
+            .find_commit(base_id)
+            .unwrap_or_else(|e| panic!("find git commit failed: {e:?}"));
+        let feature_id = repo
+            .commit(None, &sig, &sig, "Add feature\n\nDetails", &tree, &[&base])
+            .unwrap_or_else(|e| panic!("create git commit failed: {e:?}"));
+        let feature = repo
+            .find_commit(feature_id)
+            .unwrap_or_else(|e| panic!("find git commit failed: {e:?}"));
+        let merge_id = repo
+            .commit(
+                Some("HEAD"),
+                &sig,
+                &sig,
+                "Merge branch 'feature'",
+                &tree,
+                &[&base, &feature],
+            )
+            .unwrap_or_else(|e| panic!("create git commit failed: {e:?}"));
+        drop((tree, base, feature));
+
+        let repo: GitRepository = repo.into();
+        let detail = repo
+            .get_commit(merge_id.into(), true)
+            .unwrap_or_else(|e| panic!("get_commit in git repo {path:?} should not fail: {e:?}"));
+        assert!(detail.commit.is_merge);
+        assert!(detail.commit.stats.is_some());
+        assert_eq!(detail.commit.parent_count, 2);
+        let merge = detail
+            .merge
+            .unwrap_or_else(|| panic!("merge commit should have merge info"));
+        assert_eq!(merge.branch.as_deref(), Some("feature"));
+        assert_eq!(merge.id.0, feature_id);
+        assert_eq!(merge.summary.to_string(), "Add feature");
+
+        let detail = repo
+            .get_commit(base_id.into(), true)
+            .unwrap_or_else(|e| panic!("get_commit in git repo {path:?} should not fail: {e:?}"));
+        assert!(!detail.commit.is_merge);
+        assert!(detail.merge.is_none());
+        assert!(detail.signature.is_none());
+
+        let buffer = repo
+            .repo
+            .find_commit(base_id)
+            .and_then(|c| {
+                let tree = c.tree()?;
+                let sig = c.author();
+                repo.repo
+                    .commit_create_buffer(&sig, &sig, "Signed", &tree, &[&c])
+            })
+            .unwrap_or_else(|e| panic!("create git commit buffer failed: {e:?}"));
+        let signed_id = repo
+            .repo
+            .commit_signed(
+                &String::from_utf8_lossy(&buffer),
+                "-----BEGIN SSH SIGNATURE-----\nU1NIU0lH\n-----END SSH SIGNATURE-----",
+                None,
+            )
+            .unwrap_or_else(|e| panic!("create signed git commit failed: {e:?}"));
+        let detail = repo
+            .get_commit(signed_id.into(), true)
+            .unwrap_or_else(|e| panic!("get_commit in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(detail.signature, Some(model::GitCommitSignature::Ssh));
+    }
+
+    #[test]
+    fn test_merge() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        let readme = Path::new("README.md");
+        create_file_with_content(path.join(readme), "wit\n");
+        set_git_head_to_branch(&repo, "release");
+        let tree_id = write_index_tree(&repo, &[readme]);
+        let initial =
+            commit_with_signature(&repo, tree_id, "Initial", "wit", "wit@example.com", None);
+        for branch in ["conflict", "feature", "ff"] {
+            repo.find_commit(initial)
+                .and_then(|c| repo.branch(branch, &c, false))
+                .unwrap_or_else(|e| panic!("create branch failed: {e:?}"));
+        }
+        create_file_with_content(path.join(readme), "wit 2\n");
+        let tree_id = write_index_tree(&repo, &[readme]);
+        let release = commit_with_signature(
+            &repo,
+            tree_id,
+            "Bump version",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+        set_git_head_to_branch(&repo, "conflict");
+        create_file_with_content(path.join(readme), "wat\n");
+        let tree_id = write_index_tree(&repo, &[readme]);
+        commit_with_signature(&repo, tree_id, "Rename", "wit", "wit@example.com", None);
+        set_git_head_to_branch(&repo, "feature");
+        create_file_with_content(path.join(readme), "wit\n");
+        create_file_with_content(path.join("a.txt"), "a\n");
+        let tree_id = write_index_tree(&repo, &[readme, Path::new("a.txt")]);
+        let feature =
+            commit_with_signature(&repo, tree_id, "Add a", "wit", "wit@example.com", None);
+        repo.find_commit(release)
+            .and_then(|c| repo.branch("squash", &c, false))
+            .unwrap_or_else(|e| panic!("create branch failed: {e:?}"));
+        set_git_head_to_branch(&repo, "main");
+
+        let repo: GitRepository = repo.into();
+        let merge = |base: &str, head: &str, strategy: MergeStrategy| {
+            repo.merge(base, head, strategy).unwrap_or_else(|e| {
+                panic!("merge {head} into {base} in git repo {path:?} should not fail: {e:?}")
+            })
+        };
+        let tip = |reference: &str| {
+            repo.repo
+                .revparse_single(reference)
+                .and_then(|o| o.peel_to_commit())
+                .unwrap_or_else(|e| panic!("resolve {reference} failed: {e:?}"))
+        };
+
+        assert!(matches!(
+            repo.merge("release", "feature", MergeStrategy::FfOnly),
+            Err(GitError::Conflict(_))
+        ));
+        merge("ff", "feature", MergeStrategy::FfOnly);
+        assert_eq!(tip("ff").id(), feature);
+        merge("ff", "feature", MergeStrategy::MergeCommit);
+        assert_eq!(tip("ff").id(), feature);
+
+        merge("release", "feature", MergeStrategy::MergeCommit);
+        let merged = tip("release");
+        assert_eq!(merged.parent_ids().collect::<Vec<_>>(), [release, feature]);
+        assert!(merged.tree().is_ok_and(|t| t.get_name("a.txt").is_some()));
+
+        merge("squash", "feature", MergeStrategy::Squash);
+        let squashed = tip("squash");
+        assert_eq!(squashed.parent_ids().collect::<Vec<_>>(), [release]);
+        assert_eq!(
+            squashed.message(),
+            Some("Squashed feature into squash\n\n* Add a\n")
+        );
+
+        let result = merge("release", "conflict", MergeStrategy::MergeCommit);
+        assert!(result.commit.is_none());
+        assert_eq!(result.conflicts, ["README.md"]);
+        assert_eq!(tip("release").id(), merged.id());
+    }
+
+    #[test]
+    fn test_merged_branch() {
+        let sample = [
+            ("Merge branch 'feature/x' into main", Some("feature/x")),
+            (
+                "Merge remote-tracking branch 'origin/dev'",
+                Some("origin/dev"),
+            ),
+            (
+                "Merge pull request #42 from wit/fix-blame\n\nFix blame",
+                Some("wit/fix-blame"),
+            ),
+            ("Fix typo", None),
+        ];
+        for (message, branch) in sample.into_iter() {
+            assert_eq!(merged_branch(message).as_deref(), branch);
+        }
     }
 
     #[test]
@@ -481,7 +2409,7 @@ mod tests {
         );
 
         let repo: GitRepository = repo.into();
-        repo.get_blob(GitOid(Oid::zero()))
+        repo.get_blob(GitOid(Oid::zero()), None, None, usize::MAX)
             .expect_err("get_blob(all_zero_oid) in git repo {path:?} is expected to fail");
 
         let entries = repo
@@ -492,7 +2420,7 @@ mod tests {
         let item = &entries[0];
 
         let blob = repo
-            .get_blob(item.id.clone())
+            .get_blob(item.id.clone(), Some(file_name), None, usize::MAX)
             .unwrap_or_else(|e| panic!("get_blob in git repo {path:?} should not fail: {e:?}"));
 
         if let GitBlobContent::Text(s) = blob.content {
@@ -501,26 +2429,236 @@ mod tests {
             panic!("blob content should be text")
         }
         assert!(!blob.is_binary);
+        assert_eq!(blob.encoding.as_deref(), Some("UTF-8"));
+        assert!(!blob.has_crlf);
+        assert_eq!(blob.line_count, 1);
         assert!(blob.short_id.len() >= 7);
         assert_eq!(blob.size, content.len());
+        assert!(!blob.truncated);
+        assert_eq!(blob.raw_url, None);
+
+        let blob = repo
+            .get_blob(item.id.clone(), Some(file_name), None, 3)
+            .unwrap_or_else(|e| panic!("get_blob in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(blob.content.to_string(), "git");
+        assert_eq!(blob.line_count, 1);
+        assert_eq!(blob.size, content.len());
+        assert!(blob.truncated);
+        assert_eq!(
+            blob.raw_url,
+            Some(format!("/api/v1/git/blobs/{}/raw", item.id))
+        );
+
+        let raw = repo
+            .get_raw_blob(item.id.clone())
+            .unwrap_or_else(|e| panic!("get_raw_blob in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(raw, content.as_bytes());
+
+        let repo = repo.with_settings(Arc::new(GitSettings {
+            memory_budget: 3,
+            ..Default::default()
+        }));
+        for result in [
+            repo.get_blob(item.id.clone(), Some(file_name), None, usize::MAX)
+                .map(drop),
+            repo.get_raw_blob(item.id.clone()).map(drop),
+        ] {
+            assert!(matches!(result, Err(GitError::TooLarge(_))), "{result:?}");
+        }
     }
 
     #[test]
-    fn test_list_branch() {
+    fn test_get_blob_batch() {
         let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
         let path = dir.path();
         let repo = initialize_git_repo(path);
 
-        let branch = "main";
-        set_git_head_to_branch(&repo, branch);
-        let tree_id = write_index_tree(&repo, &[]);
-        commit_with_signature(
-            &repo,
-            tree_id,
-            "Initial commit",
-            "wit",
-            "wit@example.com",
-            None,
+        create_file_with_content(path.join("README.md"), "wit\n");
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        let commit_id =
+            commit_with_signature(&repo, tree_id, "Initial", "wit", "wit@example.com", None);
+
+        let repo: GitRepository = repo.into();
+        let specs = serde_json::from_str::<Vec<BlobSpec>>(&format!(
+            r#"[{{"ref": "main", "path": "README.md"}}, {{"id": "{commit_id}"}}, {{"ref": "main", "path": "missing"}}]"#
+        ))
+        .unwrap_or_else(|e| panic!("parse blob specs failed: {e:?}"));
+        let results = repo.get_blob_batch(specs, usize::MAX).unwrap_or_else(|e| {
+            panic!("get_blob_batch in git repo {path:?} should not fail: {e:?}")
+        });
+        assert_eq!(results.len(), 3);
+        match &results[0] {
+            Ok(GitBlob {
+                content: GitBlobContent::Text(s),
+                ..
+            }) => assert_eq!(s.to_string(), "wit\n"),
+            result => panic!("README.md should be a text blob: {result:?}"),
+        }
+        assert!(matches!(results[1], Err(GitError::ObjectNotFound(_))));
+        assert!(results[2].is_err());
+
+        let specs = (0..=batch::MAX_BATCH_ITEMS)
+            .map(|_| BlobSpec::Id {
+                id: commit_id.into(),
+            })
+            .collect();
+        repo.get_blob_batch(specs, usize::MAX)
+            .expect_err("get_blob_batch over the item limit should fail");
+    }
+
+    #[test]
+    fn test_get_commit_batch() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let first = commit_with_signature(&repo, tree_id, "First", "wit", "wit@example.com", None);
+        let second =
+            commit_with_signature(&repo, tree_id, "Second", "wit", "wit@example.com", None);
+
+        let repo: GitRepository = repo.into();
+        let ids = [second, Oid::zero(), first, tree_id].map(Into::into).into();
+        let results = repo.get_commit_batch(ids, true).unwrap_or_else(|e| {
+            panic!("get_commit_batch in git repo {path:?} should not fail: {e:?}")
+        });
+        let summaries = results
+            .iter()
+            .map(|r| r.as_ref().ok().map(|c| c.message.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summaries,
+            [
+                Some(String::from("Second")),
+                None,
+                Some(String::from("First")),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_object() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        create_file_with_content(path.join("README.md"), "wit\n");
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        let commit_id =
+            commit_with_signature(&repo, tree_id, "Initial", "wit", "wit@example.com", None);
+        let blob_id = repo
+            .find_tree(tree_id)
+            .ok()
+            .and_then(|t| t.get_name("README.md").map(|e| e.id()))
+            .unwrap_or_else(|| panic!("README.md should be in the tree"));
+
+        let repo: GitRepository = repo.into();
+        let get = |id: Oid| {
+            repo.get_object(id.into()).unwrap_or_else(|e| {
+                panic!("get_object in git repo {path:?} should not fail: {e:?}")
+            })
+        };
+        let tree = get(tree_id);
+        assert!(matches!(tree.kind, GitObjectType::Tree));
+        assert_eq!(
+            tree.content.to_string(),
+            format!("100644 blob {blob_id}\tREADME.md\n")
+        );
+        let commit = get(commit_id);
+        assert!(matches!(commit.kind, GitObjectType::Commit));
+        assert!(commit
+            .content
+            .to_string()
+            .starts_with(&format!("tree {tree_id}\n")));
+        assert_eq!(get(blob_id).content.to_string(), "wit\n");
+
+        let raw = repo.get_raw_object(commit_id.into()).unwrap_or_else(|e| {
+            panic!("get_raw_object in git repo {path:?} should not fail: {e:?}")
+        });
+        assert_eq!(raw.len(), commit.size);
+        repo.get_object(Oid::zero().into())
+            .expect_err("get_object of a missing object should fail");
+
+        repo.check_object(&blob_id.into(), Some(GitObjectType::Blob))
+            .unwrap_or_else(|e| panic!("check_object in git repo {path:?} should not fail: {e:?}"));
+        repo.check_object(&blob_id.into(), Some(GitObjectType::Commit))
+            .expect_err("check_object of a blob as a commit should fail");
+        for (spec, expected) in [("main", tree_id), ("main:README.md", blob_id)] {
+            let id = repo.resolve_tree(spec).unwrap_or_else(|e| {
+                panic!("resolve_tree in git repo {path:?} should not fail: {e:?}")
+            });
+            assert_eq!(id.0, expected, "{spec}");
+        }
+        repo.resolve_tree("main:missing.md")
+            .expect_err("resolve_tree of a missing path should fail");
+    }
+
+    #[test]
+    fn test_list_manifest() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        let files = ["README.md", "src/lib.rs", "src/main.rs"];
+        for file in files {
+            create_file_with_content(path.join(file), file);
+        }
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &files.map(Path::new));
+        commit_with_signature(&repo, tree_id, "Initial", "wit", "wit@example.com", None);
+
+        let repo: GitRepository = repo.into();
+        let list = |recursive, offset, limit| {
+            repo.list_manifest("main", recursive, offset, limit)
+                .unwrap_or_else(|e| {
+                    panic!("list_manifest in git repo {path:?} should not fail: {e:?}")
+                })
+        };
+        let paths = |m: &GitManifest| {
+            m.entries
+                .iter()
+                .map(|e| e.path.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let manifest = list(false, 0, MAX_MANIFEST_ENTRIES);
+        assert_eq!(paths(&manifest), ["README.md", "src"]);
+        assert_eq!(manifest.entries[0].size, Some(9));
+        assert_eq!(manifest.entries[1].size, None);
+
+        let manifest = list(true, 0, MAX_MANIFEST_ENTRIES);
+        assert_eq!(paths(&manifest), files);
+        assert_eq!(manifest.total, 3);
+        assert_eq!(manifest.next_cursor, None);
+
+        let manifest = list(true, 1, 1);
+        assert_eq!(paths(&manifest), ["src/lib.rs"]);
+        assert_eq!(manifest.next_cursor, Some(2));
+
+        repo.list_manifest("missing", true, 0, 1)
+            .expect_err("list_manifest of a missing reference should fail");
+    }
+
+    #[test]
+    fn test_list_branch() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        let branch = "main";
+        set_git_head_to_branch(&repo, branch);
+        let tree_id = write_index_tree(&repo, &[]);
+        commit_with_signature(
+            &repo,
+            tree_id,
+            "Initial commit",
+            "wit",
+            "wit@example.com",
+            None,
         );
 
         let repo: GitRepository = repo.into();
@@ -534,7 +2672,141 @@ mod tests {
         assert_eq!(item.name.to_string(), format!("refs/heads/{branch}"));
         assert_eq!(item.shorthand.to_string(), branch);
         assert!(item.target_short.len() >= 7);
+        assert_eq!(
+            item.tip.as_ref().map(|c| c.message.to_string()).as_deref(),
+            Some("Initial commit")
+        );
         assert!(item.upstream.is_none());
+
+        let entries = repo
+            .list_branch_matching(&ListBranchOptions {
+                contains: None,
+                kind: Some(GitBranchType::Remote),
+            })
+            .unwrap_or_else(|e| {
+                panic!("list_branch_matching in git repo {path:?} should not fail: {e:?}")
+            });
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn test_list_branch_containing() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let base_id = commit_with_signature(&repo, tree_id, "Base", "wit", "wit@example.com", None);
+        let base = repo
+            .find_commit(base_id)
+            .unwrap_or_else(|e| panic!("find git commit failed: {e:?}"));
+        repo.branch("release", &base, false)
+            .unwrap_or_else(|e| panic!("create git branch failed: {e:?}"));
+        drop(base);
+        let head_id = commit_with_signature(&repo, tree_id, "Next", "wit", "wit@example.com", None);
+
+        let repo: GitRepository = repo.into();
+        for (id, expected) in [(base_id, vec!["main", "release"]), (head_id, vec!["main"])] {
+            let entries = repo.list_branch_containing(id.into()).unwrap_or_else(|e| {
+                panic!("list_branch_containing in git repo {path:?} should not fail: {e:?}")
+            });
+            let mut names = entries
+                .iter()
+                .map(|b| b.shorthand.to_string())
+                .collect::<Vec<_>>();
+            names.sort();
+            assert_eq!(names, expected);
+        }
+
+        repo.list_branch_containing(Oid::zero().into())
+            .expect_err("list_branch_containing of a missing commit should fail");
+    }
+
+    #[test]
+    fn test_list_cherry() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        create_file_with_content(path.join("README.md"), "git\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        let base_id = commit_with_signature(&repo, tree_id, "Base", "wit", "wit@example.com", None);
+        let base = repo
+            .find_commit(base_id)
+            .unwrap_or_else(|e| panic!("find git commit failed: {e:?}"));
+        repo.branch("release", &base, false)
+            .unwrap_or_else(|e| panic!("create git branch failed: {e:?}"));
+        drop(base);
+
+        create_file_with_content(path.join("README.md"), "git + web\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        let fix_id = commit_with_signature(&repo, tree_id, "Fix", "wit", "wit@example.com", None);
+        create_file_with_content(path.join("LICENSE"), "MIT\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("LICENSE")]);
+        let feature_id =
+            commit_with_signature(&repo, tree_id, "Feature", "wit", "wit@example.com", None);
+
+        set_git_head_to_branch(&repo, "release");
+        let tree = repo
+            .find_commit(fix_id)
+            .and_then(|c| c.tree())
+            .unwrap_or_else(|e| panic!("find git tree failed: {e:?}"));
+        let sig = Signature::now("wit", "wit@example.com")
+            .unwrap_or_else(|e| panic!("create git signature failed: {e:?}"));
+        let base = repo
+            .find_commit(base_id)
+            .unwrap_or_else(|e| panic!("find git commit failed: {e:?}"));
+        repo.commit(
+            Some("refs/heads/release"),
+            &sig,
+            &sig,
+            "Backport fix",
+            &tree,
+            &[&base],
+        )
+        .unwrap_or_else(|e| panic!("create git commit failed: {e:?}"));
+        drop((tree, base));
+
+        let repo: GitRepository = repo.into();
+        let entries = repo
+            .list_cherry("release", "main", true)
+            .unwrap_or_else(|e| panic!("list_cherry in git repo {path:?} should not fail: {e:?}"));
+        let ids = entries.iter().map(|c| c.commit.id.0).collect::<Vec<_>>();
+        assert_eq!(ids, [fix_id, feature_id]);
+        assert!(entries[0].equivalent.is_some());
+        assert!(entries[1].equivalent.is_none());
+    }
+
+    #[test]
+    fn test_list_code_owner() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        let file_name = ".github/CODEOWNERS";
+        create_file_with_content(path.join(file_name), "* @wit/core\n*.rs @wit/rust\n");
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[Path::new(file_name)]);
+        commit_with_signature(
+            &repo,
+            tree_id,
+            "Initial commit",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+
+        let repo: GitRepository = repo.into();
+        let entries = repo.list_code_owner("src/main.rs").unwrap_or_else(|e| {
+            panic!("list_code_owner in git repo {path:?} should not fail: {e:?}")
+        });
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].owners, ["@wit/rust"]);
+        assert_eq!(entries[0].line_number, 2);
+        assert_eq!(entries[0].path, file_name);
+        assert_eq!(entries[1].owners, ["@wit/core"]);
     }
 
     #[test]
@@ -562,7 +2834,7 @@ mod tests {
 
         let repo: GitRepository = repo.into();
         let entries = repo
-            .list_commit()
+            .list_commit(Default::default())
             .unwrap_or_else(|e| panic!("list_commit in git repo {path:?} should not fail: {e:?}"));
 
         assert_eq!(entries.len(), 1);
@@ -572,10 +2844,291 @@ mod tests {
         assert_eq!(item.committer.email.to_string(), user_email);
         assert_eq!(item.committer.name.to_string(), user_name);
         assert_eq!(item.message.to_string(), commit_message);
+        assert_eq!(item.parent_count, 0);
+        assert!(item.parents.is_empty());
         assert!(item.short_id.len() >= 7);
         assert_eq!(item.time, now);
     }
 
+    #[test]
+    fn test_list_commit_page() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        for n in 0..=MAX_COMMIT_COUNT {
+            commit_with_signature(
+                &repo,
+                tree_id,
+                &format!("Commit {n}"),
+                "wit",
+                "wit@example.com",
+                None,
+            );
+        }
+
+        let repo: GitRepository = repo.into();
+        let list_page = |offset: usize| {
+            repo.list_commit_page(ListCommitOptions {
+                limit: MAX_COMMIT_COUNT,
+                offset,
+                ..Default::default()
+            })
+            .unwrap_or_else(|e| {
+                panic!("list_commit_page in git repo {path:?} should not fail: {e:?}")
+            })
+        };
+        let (commits, has_more) = list_page(0);
+        assert_eq!(commits.len(), MAX_COMMIT_COUNT);
+        assert!(has_more);
+        let (commits, has_more) = list_page(MAX_COMMIT_COUNT);
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].message.to_string(), "Commit 0");
+        assert!(!has_more);
+    }
+
+    #[test]
+    fn test_list_commit_offset() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let base_id = commit_with_signature(&repo, tree_id, "Base", "wit", "wit@example.com", None);
+        let main_id = commit_with_signature(&repo, tree_id, "Main", "wit", "wit@example.com", None);
+        let signature = Signature::now("wit", "wit@example.com")
+            .unwrap_or_else(|e| panic!("create git signature failed: {e:?}"));
+        let find = |id| {
+            repo.find_commit(id)
+                .unwrap_or_else(|e| panic!("find git commit failed: {e:?}"))
+        };
+        let tree = repo
+            .find_tree(tree_id)
+            .unwrap_or_else(|e| panic!("find git tree failed: {e:?}"));
+        let side_id = repo
+            .commit(
+                None,
+                &signature,
+                &signature,
+                "Side",
+                &tree,
+                &[&find(base_id)],
+            )
+            .unwrap_or_else(|e| panic!("create git commit failed: {e:?}"));
+        let merge_id = repo
+            .commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                "Merge",
+                &tree,
+                &[&find(main_id), &find(side_id)],
+            )
+            .unwrap_or_else(|e| panic!("create git commit failed: {e:?}"));
+        drop(tree);
+
+        let repo: GitRepository = repo.into();
+        commit_with_signature(&repo.repo, tree_id, "Later", "wit", "wit@example.com", None);
+        let mut ids = vec![];
+        for offset in (0..6).step_by(2) {
+            let page = repo
+                .list_commit(ListCommitOptions {
+                    limit: 2,
+                    offset,
+                    start: Some(merge_id.into()),
+                    ..Default::default()
+                })
+                .unwrap_or_else(|e| {
+                    panic!("list_commit in git repo {path:?} should not fail: {e:?}")
+                });
+            ids.extend(page.into_iter().map(|c| c.id.0));
+        }
+        ids.sort();
+        let mut expected = vec![base_id, main_id, side_id, merge_id];
+        expected.sort();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn test_list_commit_deadline() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        commit_with_signature(&repo, tree_id, "Initial", "wit", "wit@example.com", None);
+
+        let mut repo: GitRepository = repo.into();
+        repo.deadline = Deadline::expired();
+        let result = repo.list_commit(Default::default());
+        assert!(matches!(result, Err(GitError::Timeout)), "{result:?}");
+        let result = repo.list_path(&repo.resolve_commit("HEAD").unwrap_or_else(|e| {
+            panic!("resolve HEAD in git repo {path:?} should not fail: {e:?}")
+        }));
+        assert!(matches!(result, Err(GitError::Timeout)), "{result:?}");
+        let result = repo.search_commit(SearchCommitOptions {
+            pickaxe: Some(String::from("wit")),
+            ..Default::default()
+        });
+        assert!(matches!(result, Err(GitError::Timeout)), "{result:?}");
+
+        let (deadline, cancel) = Deadline::after(std::time::Duration::from_secs(60));
+        let repo = repo.with_deadline(deadline);
+        assert!(repo.list_commit(Default::default()).is_ok());
+        drop(cancel);
+        let result = repo.list_commit(Default::default());
+        assert!(matches!(result, Err(GitError::Cancelled)), "{result:?}");
+    }
+
+    #[test]
+    fn test_list_commit_shallow() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let [_, second, third] = ["first", "second", "third"].map(|message| {
+            commit_with_signature(&repo, tree_id, message, "wit", "wit@example.com", None)
+        });
+        std::fs::write(path.join(".git/shallow"), format!("{second}\n"))
+            .unwrap_or_else(|e| panic!("write shallow file failed: {e:?}"));
+
+        let repo = GitRepository::open(path)
+            .unwrap_or_else(|e| panic!("open git repo {path:?} should not fail: {e:?}"));
+        let entries = repo
+            .list_commit(Default::default())
+            .unwrap_or_else(|e| panic!("list_commit in git repo {path:?} should not fail: {e:?}"));
+        let ids: Vec<_> = entries
+            .iter()
+            .map(|c| (c.id.0, c.truncated_history))
+            .collect();
+        assert_eq!(ids, [(third, false), (second, true)]);
+    }
+
+    #[test]
+    fn test_worktree() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path().join("main");
+        let repo = initialize_git_repo(&path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        commit_with_signature(&repo, tree_id, "Initial", "wit", "wit@example.com", None);
+        let linked = dir.path().join("linked");
+        repo.worktree("feature", &linked, None)
+            .unwrap_or_else(|e| panic!("add git worktree failed: {e:?}"));
+
+        let main: GitRepository = repo.into();
+        assert!(matches!(main.worktree(), Ok(None)));
+        let worktree = GitRepository::open(&linked)
+            .and_then(|r| r.worktree())
+            .unwrap_or_else(|e| panic!("worktree in git repo {linked:?} should not fail: {e:?}"))
+            .unwrap_or_else(|| panic!("{linked:?} should be a linked worktree"));
+        assert_eq!(worktree.branch.as_deref(), Some("feature"));
+        assert_eq!(worktree.name.as_deref(), Some("feature"));
+    }
+
+    #[test]
+    fn test_list_commit_until() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let commit_ids = ["first", "second", "third"].map(|message| {
+            commit_with_signature(&repo, tree_id, message, "wit", "wit@example.com", None)
+        });
+
+        let repo: GitRepository = repo.into();
+        let entries = repo
+            .list_commit(Default::default())
+            .unwrap_or_else(|e| panic!("list_commit in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].stats.is_none());
+
+        let options = ListCommitOptions {
+            until: Some(commit_ids[0].into()),
+            with_stats: true,
+            ..Default::default()
+        };
+        let entries = repo
+            .list_commit(options)
+            .unwrap_or_else(|e| panic!("list_commit in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].message.to_string(), "third");
+        assert_eq!(entries[1].message.to_string(), "second");
+        assert_eq!(entries[1].parent_count, 1);
+        assert_eq!(entries[1].parents[0].0, commit_ids[0]);
+        assert_eq!(entries[1].stats.as_ref().map(|s| s.files_changed), Some(0));
+
+        let options = ListCommitOptions {
+            exclude: Some(commit_ids[0].into()),
+            start: Some(commit_ids[1].into()),
+            ..Default::default()
+        };
+        let entries = repo
+            .list_commit(options)
+            .unwrap_or_else(|e| panic!("list_commit in git repo {path:?} should not fail: {e:?}"));
+        let ids = entries.iter().map(|c| c.id.0).collect::<Vec<_>>();
+        assert_eq!(ids, [commit_ids[1]]);
+
+        let options = ListCommitOptions {
+            limit: 1,
+            offset: 1,
+            ..Default::default()
+        };
+        let entries = repo
+            .list_commit(options)
+            .unwrap_or_else(|e| panic!("list_commit in git repo {path:?} should not fail: {e:?}"));
+        let ids = entries.iter().map(|c| c.id.0).collect::<Vec<_>>();
+        assert_eq!(ids, [commit_ids[1]]);
+    }
+
+    #[test]
+    fn test_list_commit_mailmap() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        let file_name = ".mailmap";
+        create_file_with_content(
+            path.join(file_name),
+            "Wit <wit@example.com> <old@example.com>\n",
+        );
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[Path::new(file_name)]);
+        commit_with_signature(&repo, tree_id, "Old", "wit", "old@example.com", None);
+        commit_with_signature(&repo, tree_id, "New", "Wit", "wit@example.com", None);
+
+        let repo: GitRepository = repo.into();
+        let authors = |mailmap| {
+            let options = ListCommitOptions {
+                mailmap,
+                ..Default::default()
+            };
+            repo.list_commit(options)
+                .unwrap_or_else(|e| {
+                    panic!("list_commit in git repo {path:?} should not fail: {e:?}")
+                })
+                .iter()
+                .map(|c| format!("{} <{}>", c.author.name, c.author.email))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            authors(true),
+            ["Wit <wit@example.com>", "Wit <wit@example.com>"]
+        );
+        assert_eq!(
+            authors(false),
+            ["Wit <wit@example.com>", "wit <old@example.com>"]
+        );
+    }
+
     #[test]
     fn test_list_index() {
         let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
@@ -606,38 +3159,138 @@ mod tests {
         write_index_tree(&repo, &file_list.map(Path::new));
 
         let repo: GitRepository = repo.into();
-        let sample = [
-            (Default::default(), 5, Default::default()),
-            ("dir01/dir12/dir23", 3, "dir01/dir12/dir23/"),
-            ("dir01/dir12/dir23/", 3, "dir01/dir12/dir23/"),
-            ("dir01/file1", 1, "dir01/"),
-        ];
-        for (index, count, root) in sample.into_iter() {
-            let entries = repo.list_index(index).unwrap_or_else(|e| {
-                panic!("list_index in git repo {path:?} should not fail: {e:?}")
-            });
-            assert_eq!(entries.len(), count);
-            for item in entries.iter() {
-                let full_path = match item {
-                    GitIndex::Directory(d) => &d.path,
-                    GitIndex::Entry(e) => {
-                        assert_eq!(e.file_size, 0);
-                        &e.path
-                    }
-                };
-                if !index.is_empty() {
-                    assert!(
-                        full_path.0.starts_with(root),
-                        "unexpected root of index entry"
-                    );
-                }
-                assert_eq!(
-                    full_path.0.split('/').count(),
-                    root.split('/').count(),
-                    "unexpected root of index entry"
-                )
-            }
-        }
+        let sample = [
+            (Default::default(), 5, Default::default()),
+            ("dir01/dir12/dir23", 3, "dir01/dir12/dir23/"),
+            ("dir01/dir12/dir23/", 3, "dir01/dir12/dir23/"),
+            ("dir01/file1", 1, "dir01/"),
+        ];
+        for (index, count, root) in sample.into_iter() {
+            let entries = repo.list_index(index).unwrap_or_else(|e| {
+                panic!("list_index in git repo {path:?} should not fail: {e:?}")
+            });
+            assert_eq!(entries.len(), count);
+            for item in entries.iter() {
+                let full_path = match item {
+                    GitIndex::Directory(d) => &d.path,
+                    GitIndex::Entry(e) => {
+                        assert_eq!(e.file_size, 0);
+                        assert_eq!(e.short_id.len(), 7);
+                        assert!(e.id.to_string().starts_with(&e.short_id));
+                        &e.path
+                    }
+                };
+                if !index.is_empty() {
+                    assert!(
+                        full_path.0.starts_with(root),
+                        "unexpected root of index entry"
+                    );
+                }
+                assert_eq!(
+                    full_path.0.split('/').count(),
+                    root.split('/').count(),
+                    "unexpected root of index entry"
+                )
+            }
+        }
+
+        repo.repo
+            .config()
+            .and_then(|mut c| c.set_i32("core.abbrev", 12))
+            .unwrap_or_else(|e| panic!("set git config failed: {e:?}"));
+        let entries = repo
+            .list_index("dir01/file1")
+            .unwrap_or_else(|e| panic!("list_index in git repo {path:?} should not fail: {e:?}"));
+        match &entries[0] {
+            GitIndex::Entry(e) => assert_eq!(e.short_id.len(), 12),
+            GitIndex::Directory(_) => panic!("index entry should not be a directory"),
+        }
+    }
+
+    #[test]
+    fn test_list_license() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        let file_list = [
+            (
+                "LICENSE-APACHE",
+                "Apache License\nVersion 2.0, January 2004\n",
+            ),
+            (
+                "LICENSE-MIT",
+                "Permission is hereby granted, free of charge, ...\n\
+                 THE SOFTWARE IS PROVIDED \"AS IS\", WITHOUT WARRANTY OF ANY KIND\n",
+            ),
+            ("COPYING", "All rights reserved.\n"),
+            ("README.md", "git + web = wit\n"),
+        ];
+        for (file_name, content) in file_list.iter() {
+            create_file_with_content(path.join(file_name), content);
+        }
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &file_list.map(|(f, _)| Path::new(f)));
+        commit_with_signature(
+            &repo,
+            tree_id,
+            "Initial commit",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+
+        let repo: GitRepository = repo.into();
+        let entries = repo
+            .list_license()
+            .unwrap_or_else(|e| panic!("list_license in git repo {path:?} should not fail: {e:?}"));
+        let licenses = entries
+            .iter()
+            .map(|l| (l.path.to_string(), l.spdx_id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            licenses,
+            [
+                ("LICENSE-APACHE".into(), "Apache-2.0"),
+                ("LICENSE-MIT".into(), "MIT")
+            ]
+        );
+
+        let stats = repo.gather_tree_stats().unwrap_or_else(|e| {
+            panic!("gather_tree_stats in git repo {path:?} should not fail: {e:?}")
+        });
+        assert_eq!(stats.license.as_deref(), Some("Apache-2.0 OR MIT"));
+    }
+
+    #[test]
+    fn test_list_path() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        let file_list = ["dir01/dir11/file1", "dir01/file1", "file1"];
+        for &file_name in file_list.iter() {
+            create_file_with_content(path.join(file_name), "");
+        }
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &file_list.map(Path::new));
+        let commit_id = commit_with_signature(
+            &repo,
+            tree_id,
+            "Initial commit",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+
+        let repo: GitRepository = repo.into();
+        let entries = repo
+            .list_path(&commit_id.into())
+            .unwrap_or_else(|e| panic!("list_path in git repo {path:?} should not fail: {e:?}"));
+
+        assert_eq!(entries, file_list);
     }
 
     #[test]
@@ -669,6 +3322,48 @@ mod tests {
         assert_eq!(item.name.to_string(), format!("refs/heads/{branch}"));
         assert_eq!(item.shorthand.to_string(), branch);
         assert!(item.target_short.len() >= 7);
+
+        let sample = [
+            (Some(RefNamespace::Heads), None, 1),
+            (Some(RefNamespace::Tags), None, 0),
+            (None, Some("mai*"), 1),
+            (Some(RefNamespace::Heads), Some("dev*"), 0),
+        ];
+        for (namespace, pattern, expected) in sample.into_iter() {
+            let opts = ListReferenceOptions {
+                namespace,
+                pattern: pattern.map(String::from),
+            };
+            let entries = repo.list_reference_matching(&opts).unwrap_or_else(|e| {
+                panic!("list_reference_matching in git repo {path:?} should not fail: {e:?}")
+            });
+            assert_eq!(entries.len(), expected, "{opts:?}");
+        }
+    }
+
+    #[test]
+    fn test_list_reference_at() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let base_id = commit_with_signature(&repo, tree_id, "Base", "wit", "wit@example.com", None);
+        create_tag_for_commit(&repo, "v1.0.0", base_id);
+        let head_id = commit_with_signature(&repo, tree_id, "Next", "wit", "wit@example.com", None);
+
+        let repo: GitRepository = repo.into();
+        for (id, expected) in [(base_id, "v1.0.0"), (head_id, "main")] {
+            let entries = repo.list_reference_at(&id.into()).unwrap_or_else(|e| {
+                panic!("list_reference_at in git repo {path:?} should not fail: {e:?}")
+            });
+            let names = entries
+                .iter()
+                .map(|r| r.shorthand.to_string())
+                .collect::<Vec<_>>();
+            assert_eq!(names, [expected]);
+        }
     }
 
     #[test]
@@ -691,6 +3386,157 @@ mod tests {
         let item = &entries[0];
         assert_eq!(item.name.to_string(), remote_name);
         assert_eq!(item.url.to_string(), remote_url);
+        assert!(item.fetched_at.is_none());
+    }
+
+    #[test]
+    fn test_fetch_remote() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let upstream_path = dir.path().join("upstream");
+        let upstream = initialize_git_repo(&upstream_path);
+        set_git_head_to_branch(&upstream, "main");
+        let tree_id = write_index_tree(&upstream, &[]);
+        let commit_id = commit_with_signature(
+            &upstream,
+            tree_id,
+            "Initial",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+
+        let path = dir.path().join("mirror");
+        let repo = initialize_git_repo(&path);
+        repo.remote("origin", &upstream_path.to_string_lossy())
+            .unwrap_or_else(|e| panic!("add git remote failed: {e:?}"));
+
+        let repo: GitRepository = repo.into();
+        let updates = repo
+            .fetch_remote("origin")
+            .unwrap_or_else(|e| panic!("fetch_remote in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].shorthand, "origin/main");
+        assert!(updates[0].old.is_none());
+        let updates = repo
+            .fetch_remote("origin")
+            .unwrap_or_else(|e| panic!("fetch_remote in git repo {path:?} should not fail: {e:?}"));
+        assert!(updates.is_empty());
+        let target = repo.resolve_commit("origin/main").unwrap_or_else(|e| {
+            panic!("resolve_commit in git repo {path:?} should not fail: {e:?}")
+        });
+        assert_eq!(target.0, commit_id);
+        let entries = repo
+            .list_remote()
+            .unwrap_or_else(|e| panic!("list_remote in git repo {path:?} should not fail: {e:?}"));
+        assert!(entries[0].fetched_at.is_some());
+
+        repo.fetch_remote("missing")
+            .expect_err("fetch_remote of a missing remote should fail");
+    }
+
+    #[test]
+    fn test_list_remote_ref() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let upstream_path = dir.path().join("upstream");
+        let upstream = initialize_git_repo(&upstream_path);
+        set_git_head_to_branch(&upstream, "main");
+        let tree_id = write_index_tree(&upstream, &[]);
+        commit_with_signature(
+            &upstream,
+            tree_id,
+            "Initial",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+
+        let path = dir.path().join("mirror");
+        let repo = initialize_git_repo(&path);
+        repo.remote("origin", &upstream_path.to_string_lossy())
+            .unwrap_or_else(|e| panic!("add git remote failed: {e:?}"));
+
+        let repo: GitRepository = repo.into();
+        let status = |repo: &GitRepository| {
+            let refs = repo.list_remote_ref("origin").unwrap_or_else(|e| {
+                panic!("list_remote_ref in git repo {path:?} should not fail: {e:?}")
+            });
+            assert_eq!(refs.len(), 1);
+            assert_eq!(refs[0].name.to_string(), "refs/heads/main");
+            refs.into_iter().next().map(|r| r.status)
+        };
+        assert_eq!(status(&repo), Some(GitRemoteRefStatus::Missing));
+
+        repo.fetch_remote("origin")
+            .unwrap_or_else(|e| panic!("fetch_remote in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(status(&repo), Some(GitRemoteRefStatus::UpToDate));
+        let branches = repo
+            .list_branch()
+            .unwrap_or_else(|e| panic!("list_branch in git repo {path:?} should not fail: {e:?}"));
+        let branch = branches
+            .iter()
+            .find(|b| b.shorthand.to_string() == "origin/main")
+            .unwrap_or_else(|| panic!("origin/main should be listed"));
+        assert_eq!(
+            branch.remote.as_ref().map(ToString::to_string).as_deref(),
+            Some("origin")
+        );
+        assert!(branch.remote_only);
+        assert!(branch.fetched_at.is_some());
+
+        commit_with_signature(&upstream, tree_id, "Next", "wit", "wit@example.com", None);
+        assert_eq!(status(&repo), Some(GitRemoteRefStatus::Stale));
+    }
+
+    #[test]
+    fn test_verify_pack() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let commit_id =
+            commit_with_signature(&repo, tree_id, "Initial", "wit", "wit@example.com", None);
+        let mut builder = repo
+            .packbuilder()
+            .unwrap_or_else(|e| panic!("create git packbuilder failed: {e:?}"));
+        builder
+            .insert_commit(commit_id)
+            .unwrap_or_else(|e| panic!("insert git commit failed: {e:?}"));
+        let pack_dir = repo.path().join("objects").join("pack");
+        builder
+            .write(&pack_dir, 0)
+            .unwrap_or_else(|e| panic!("write git pack failed: {e:?}"));
+        drop(builder);
+
+        let repo: GitRepository = repo.into();
+        let packs = repo
+            .list_pack()
+            .unwrap_or_else(|e| panic!("list_pack in git repo {path:?} should not fail: {e:?}"));
+        assert_eq!(packs.len(), 1);
+        let pack = &packs[0];
+        assert_eq!(pack.object_count, Some(2));
+        assert!(pack.index_size.is_some());
+
+        let verification = repo
+            .verify_pack(&pack.name)
+            .unwrap_or_else(|e| panic!("verify_pack in git repo {path:?} should not fail: {e:?}"));
+        assert!(verification.valid, "{:?}", verification.error);
+
+        let pack_path = pack_dir.join(format!("{}.pack", pack.name));
+        let mut bytes = std::fs::read(&pack_path)
+            .unwrap_or_else(|e| panic!("read {pack_path:?} failed: {e:?}"));
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        std::fs::write(&pack_path, bytes)
+            .unwrap_or_else(|e| panic!("write {pack_path:?} failed: {e:?}"));
+        let verification = repo
+            .verify_pack(&pack.name)
+            .unwrap_or_else(|e| panic!("verify_pack in git repo {path:?} should not fail: {e:?}"));
+        assert!(!verification.valid);
+        assert!(verification.error.is_some());
+
+        repo.verify_pack("../config")
+            .expect_err("verify_pack of an invalid name should fail");
     }
 
     #[test]
@@ -722,6 +3568,97 @@ mod tests {
         assert_eq!(item.name.to_string(), format!("refs/tags/{tag}"));
         assert_eq!(item.shorthand.to_string(), tag);
         assert!(item.target_short.len() >= 7);
+        assert_eq!(item.commit.as_ref().map(|c| c.0), Some(commit_id));
+        assert!(item.message.is_none());
+        assert!(item.time.is_some());
+
+        for (pattern, expected) in [("v0.*", 1), ("v1.*", 0)] {
+            let entries = repo.list_tag_matching(Some(pattern)).unwrap_or_else(|e| {
+                panic!("list_tag_matching in git repo {path:?} should not fail: {e:?}")
+            });
+            assert_eq!(entries.len(), expected, "{pattern}");
+        }
+    }
+
+    #[test]
+    fn test_list_tag_containing() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let commit_ids = ["Fix", "Release", "Next", "Release"].map(|message| {
+            commit_with_signature(&repo, tree_id, message, "wit", "wit@example.com", None)
+        });
+        create_tag_for_commit(&repo, "v1.0.0", commit_ids[1]);
+        let release = repo
+            .find_commit(commit_ids[3])
+            .unwrap_or_else(|e| panic!("find git commit failed: {e:?}"));
+        let sig = Signature::now("wit", "wit@example.com")
+            .unwrap_or_else(|e| panic!("create git signature failed: {e:?}"));
+        repo.tag("v1.1.0", release.as_object(), &sig, "v1.1.0", false)
+            .unwrap_or_else(|e| panic!("create git tag failed: {e:?}"));
+        drop(release);
+
+        let repo: GitRepository = repo.into();
+        let sample = [
+            (commit_ids[0], 2, Some("v1.0.0")),
+            (commit_ids[2], 1, Some("v1.1.0")),
+        ];
+        for (id, tag_count, nearest) in sample.into_iter() {
+            let entries = repo.list_tag_containing(id.into()).unwrap_or_else(|e| {
+                panic!("list_tag_containing in git repo {path:?} should not fail: {e:?}")
+            });
+            assert_eq!(entries.tags.len(), tag_count);
+            assert_eq!(
+                entries.nearest.map(|t| t.shorthand.to_string()).as_deref(),
+                nearest
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_reference() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        let file_list = ["dir01/file1", "file1"];
+        for &file_name in file_list.iter() {
+            create_file_with_content(path.join(file_name), file_name);
+        }
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &file_list.map(Path::new));
+        let commit_id = commit_with_signature(
+            &repo,
+            tree_id,
+            "Initial commit",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+        let commit = repo
+            .find_commit(commit_id)
+            .unwrap_or_else(|e| panic!("find commit in git repo {path:?} failed: {e:?}"));
+        repo.branch("feature/x", &commit, false)
+            .unwrap_or_else(|e| panic!("create branch in git repo {path:?} failed: {e:?}"));
+        drop(commit);
+        let short_id = commit_id.to_string()[..7].to_string();
+
+        let repo: GitRepository = repo.into();
+        let sample = [
+            ("main", Some(("main", ""))),
+            ("main/dir01/file1", Some(("main", "dir01/file1"))),
+            ("feature/x/file1", Some(("feature/x", "file1"))),
+            (&format!("{short_id}/dir01"), Some((&short_id[..], "dir01"))),
+            ("dir01/file1", None),
+            ("", None),
+        ];
+        for (spec, expected) in sample.into_iter() {
+            assert_eq!(repo.split_reference(spec), expected, "split {spec:?}");
+        }
     }
 
     #[test]
@@ -747,7 +3684,7 @@ mod tests {
             "file3",
         ];
         for &file_name in file_list.iter() {
-            create_file_with_content(path.join(file_name), "");
+            create_file_with_content(path.join(file_name), file_name);
         }
 
         set_git_head_to_branch(&repo, "main");
@@ -776,10 +3713,49 @@ mod tests {
             for item in entries.iter() {
                 assert!(item.short_id.len() >= 7);
                 assert_eq!(item.root, root, "unexpected root of tree entry");
+                let size = match item.kind {
+                    Some(GitObjectType::Blob) => Some(item.root.len() + item.name.0.len()),
+                    _ => None,
+                };
+                assert_eq!(item.size, size, "unexpected size of tree entry");
             }
         }
     }
 
+    #[test]
+    fn test_list_unreleased() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let base_id = commit_with_signature(&repo, tree_id, "Base", "wit", "wit@example.com", None);
+        let repo: GitRepository = repo.into();
+        let unreleased = repo
+            .list_unreleased(Default::default())
+            .unwrap_or_else(|e| {
+                panic!("list_unreleased in git repo {path:?} should not fail: {e:?}")
+            });
+        assert_eq!(unreleased.tag, None);
+        assert_eq!(unreleased.commits.len(), 1);
+
+        create_tag_for_commit(&repo.repo, "v1.0.0", base_id);
+        commit_with_signature(&repo.repo, tree_id, "Next", "wit", "wit@example.com", None);
+        let unreleased = repo
+            .list_unreleased(Default::default())
+            .unwrap_or_else(|e| {
+                panic!("list_unreleased in git repo {path:?} should not fail: {e:?}")
+            });
+        assert_eq!(unreleased.tag.as_deref(), Some("v1.0.0"));
+        let messages: Vec<_> = unreleased
+            .commits
+            .iter()
+            .map(|c| c.message.to_string())
+            .collect();
+        assert_eq!(messages, ["Next"]);
+    }
+
     #[test]
     fn test_open_repository() {
         let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
@@ -800,4 +3776,216 @@ mod tests {
         GitRepository::open(path)
             .unwrap_or_else(|e| panic!("{path:?} should be a valid git repo: {e:?}"));
     }
+
+    #[test]
+    fn test_resolve_commit() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_index_tree(&repo, &[]);
+        let commit_id = commit_with_signature(
+            &repo,
+            tree_id,
+            "Initial commit",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+        create_tag_for_commit(&repo, "v0.0.0", commit_id);
+
+        let repo: GitRepository = repo.into();
+        let short_id = commit_id.to_string()[..7].to_string();
+        for spec in ["HEAD", "main", "v0.0.0", &short_id] {
+            let id = repo.resolve_commit(spec).unwrap_or_else(|e| {
+                panic!("resolve_commit({spec:?}) in git repo {path:?} should not fail: {e:?}")
+            });
+            assert_eq!(id.0, commit_id);
+        }
+        assert!(matches!(
+            repo.resolve_commit("missing"),
+            Err(GitError::ReferenceNotFound(_))
+        ));
+        assert!(matches!(
+            repo.resolve_commit("main^{"),
+            Err(GitError::InvalidReference(_))
+        ));
+    }
+
+    #[test]
+    fn test_revert_commit() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        create_file_with_content(path.join("README.md"), "wit\n");
+        set_git_head_to_branch(&repo, "main");
+        let first_tree = write_index_tree(&repo, &[Path::new("README.md")]);
+        commit_with_signature(&repo, first_tree, "Initial", "wit", "wit@example.com", None);
+        create_file_with_content(path.join("README.md"), "wit 2\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        let second = commit_with_signature(
+            &repo,
+            tree_id,
+            "Bump version",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+        repo.find_commit(second)
+            .and_then(|c| repo.branch("release", &c, false))
+            .unwrap_or_else(|e| panic!("create branch failed: {e:?}"));
+
+        let repo: GitRepository = repo.into();
+        let revert = repo
+            .revert_commit(second, "release", None)
+            .unwrap_or_else(|e| {
+                panic!("revert_commit in git repo {path:?} should not fail: {e:?}")
+            });
+        assert_eq!(
+            revert.message.to_string(),
+            format!("Revert \"Bump version\"\n\nThis reverts commit {second}.\n")
+        );
+        let tip = repo
+            .repo
+            .revparse_single("release^{tree}")
+            .unwrap_or_else(|e| panic!("resolve release failed: {e:?}"));
+        assert_eq!(tip.id(), first_tree);
+
+        assert!(matches!(
+            repo.revert_commit(second, "main", None),
+            Err(GitError::Conflict(_))
+        ));
+
+        let merge = repo
+            .repo
+            .find_commit(second)
+            .and_then(|c| {
+                let sig = c.author();
+                repo.repo
+                    .commit(None, &sig, &sig, "Merge", &c.tree()?, &[&c, &c.parent(0)?])
+            })
+            .unwrap_or_else(|e| panic!("create merge commit failed: {e:?}"));
+        for (id, mainline) in [(merge, None), (merge, Some(3)), (second, Some(1))] {
+            assert!(
+                matches!(
+                    repo.revert_commit(id, "release", mainline),
+                    Err(GitError::InvalidArgument(_))
+                ),
+                "{id} {mainline:?}"
+            );
+        }
+        assert!(repo.revert_commit(merge, "release", Some(1)).is_ok());
+    }
+
+    #[test]
+    fn test_search_commit() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+        set_git_head_to_branch(&repo, "main");
+
+        let revisions = [
+            ("Add README", "git + web\n"),
+            ("Mention wit", "git + web = wit\n"),
+            ("Reword README", "wit = git + web\n"),
+            ("Drop wit", "git + web\n"),
+        ];
+        let file_path = Path::new("README.md");
+        let commit_ids = revisions.map(|(message, content)| {
+            create_file_with_content(path.join(file_path), content);
+            let tree_id = write_index_tree(&repo, &[file_path]);
+            commit_with_signature(&repo, tree_id, message, "wit", "wit@example.com", None)
+        });
+
+        let repo: GitRepository = repo.into();
+        let search = |pickaxe: &str| {
+            let options = SearchCommitOptions {
+                pickaxe: Some(pickaxe.to_string()),
+                ..Default::default()
+            };
+            repo.search_commit(options).unwrap_or_else(|e| {
+                panic!("search_commit in git repo {path:?} should not fail: {e:?}")
+            })
+        };
+        let entries = search("wit");
+        let ids = entries.iter().map(|c| c.id.0).collect::<Vec<_>>();
+        assert_eq!(ids, [commit_ids[3], commit_ids[1]]);
+
+        let entries = search("");
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn test_search_commit_follow() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+        set_git_head_to_branch(&repo, "main");
+
+        let content = "git + web = wit\n".repeat(8);
+        create_file_with_content(path.join("README"), &content);
+        let tree_id = write_index_tree(&repo, &[Path::new("README")]);
+        let add_id = commit_with_signature(&repo, tree_id, "Add", "wit", "wit@example.com", None);
+
+        rename(path.join("README"), path.join("README.md"))
+            .unwrap_or_else(|e| panic!("rename file failed: {e:?}"));
+        repo.index()
+            .and_then(|mut index| {
+                index.remove_path(Path::new("README"))?;
+                index.write()
+            })
+            .unwrap_or_else(|e| panic!("remove file from git index failed: {e:?}"));
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        let rename_id =
+            commit_with_signature(&repo, tree_id, "Rename", "wit", "wit@example.com", None);
+
+        create_file_with_content(path.join("README.md"), &format!("{content}wit\n"));
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        let edit_id = commit_with_signature(&repo, tree_id, "Edit", "wit", "wit@example.com", None);
+
+        let repo: GitRepository = repo.into();
+        for (follow, expected) in [
+            (false, vec![edit_id, rename_id]),
+            (true, vec![edit_id, rename_id, add_id]),
+        ] {
+            let options = SearchCommitOptions {
+                follow,
+                path: Some("README.md".to_string()),
+                ..Default::default()
+            };
+            let entries = repo.search_commit(options).unwrap_or_else(|e| {
+                panic!("search_commit in git repo {path:?} should not fail: {e:?}")
+            });
+            let ids = entries.iter().map(|c| c.id.0).collect::<Vec<_>>();
+            assert_eq!(ids, expected, "unexpected history with follow={follow}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_spawn_repository() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path().to_path_buf();
+
+        let handle = RepoHandle::new(path.to_string_lossy().into_owned(), GitSettings::default());
+        handle
+            .clone()
+            .spawn(GitRepository::list_remote)
+            .await
+            .expect_err("{path:?} should not be a valid git repo");
+
+        let repo = initialize_git_repo(&path);
+        let remote_name = "example";
+        repo.remote(remote_name, "https://example.com/git/wit.git")
+            .unwrap_or_else(|e| panic!("add git remote failed: {e:?}"));
+
+        let entries = handle
+            .spawn(GitRepository::list_remote)
+            .await
+            .unwrap_or_else(|e| panic!("list_remote in git repo {path:?} should not fail: {e:?}"));
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].name.to_string(), remote_name);
+    }
 }
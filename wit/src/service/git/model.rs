@@ -1,14 +1,22 @@
-use git2::{BranchType, ObjectType, Oid, ReferenceType, Signature, Status};
+use git2::{BranchType, Delta, ObjectType, Oid, ReferenceType, RepositoryState, Signature, Status};
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
 use time::OffsetDateTime;
 
+use super::avatar::avatar_url;
+
 #[derive(Debug, Serialize)]
 pub(crate) struct GitBlob {
     pub(crate) content: GitBlobContent,
+    pub(crate) encoding: Option<String>,
+    pub(crate) has_crlf: bool,
     pub(crate) id: GitOid,
     pub(crate) is_binary: bool,
+    pub(crate) line_count: usize,
+    /// Where the full content can be fetched when it is `truncated`.
+    pub(crate) raw_url: Option<String>,
     pub(crate) short_id: String,
     pub(crate) size: usize,
+    pub(crate) truncated: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -26,19 +34,46 @@ impl std::fmt::Display for GitBlobContent {
     }
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct GitBlameHunk {
+    pub(crate) author: GitSignature,
+    pub(crate) commit_id: GitOid,
+    pub(crate) line_count: usize,
+    pub(crate) start_line: usize,
+    pub(crate) time: OffsetDateTime,
+}
+
 #[derive(Debug, Serialize)]
 pub(crate) struct GitBranch {
+    /// Time of the last reflog entry, i.e. the last fetch for remote branches.
+    pub(crate) fetched_at: Option<OffsetDateTime>,
     pub(crate) kind: GitBranchType,
     pub(crate) name: MaybeLossyUtf8,
+    pub(crate) remote: Option<MaybeLossyUtf8>,
+    /// Remote branch with no local branch of the same name or tracking it.
+    pub(crate) remote_only: bool,
     pub(crate) shorthand: MaybeLossyUtf8,
     pub(crate) target: GitOid,
     pub(crate) target_short: String,
+    pub(crate) tip: Option<GitCommit>,
     pub(crate) upstream: Option<GitUpstream>,
 }
 
 #[derive(Debug, Serialize)]
+pub(crate) struct GitBranchDivergence {
+    pub(crate) default_branch: Option<GitUpstream>,
+    pub(crate) name: MaybeLossyUtf8,
+    pub(crate) shorthand: MaybeLossyUtf8,
+    pub(crate) target: GitOid,
+    pub(crate) target_short: String,
+    pub(crate) upstream: Option<GitUpstream>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub(crate) enum GitBranchType {
+    #[serde(rename(deserialize = "local"))]
     Local,
+    #[serde(rename(deserialize = "remote"))]
     Remote,
 }
 
@@ -51,14 +86,247 @@ impl From<BranchType> for GitBranchType {
     }
 }
 
+impl From<GitBranchType> for BranchType {
+    fn from(t: GitBranchType) -> Self {
+        match t {
+            GitBranchType::Local => BranchType::Local,
+            GitBranchType::Remote => BranchType::Remote,
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
+pub(crate) struct GitCherry {
+    pub(crate) commit: GitCommit,
+    pub(crate) equivalent: Option<GitOid>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitCodeOwner {
+    pub(crate) line_number: usize,
+    pub(crate) owners: Vec<String>,
+    pub(crate) path: String,
+    pub(crate) pattern: String,
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub(crate) struct GitCommit {
     pub(crate) author: GitSignature,
     pub(crate) committer: GitSignature,
     pub(crate) id: GitOid,
+    pub(crate) is_merge: bool,
     pub(crate) message: MaybeLossyUtf8,
+    pub(crate) parent_count: usize,
+    pub(crate) parents: Vec<GitOid>,
     pub(crate) short_id: String,
+    pub(crate) stats: Option<GitDiffStats>,
     pub(crate) time: OffsetDateTime,
+    /// Set on the commits at the boundary of a shallow clone, whose parents
+    /// were not fetched.
+    pub(crate) truncated_history: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitDefaultBranch {
+    pub(crate) name: String,
+    pub(crate) overridden: bool,
+    pub(crate) target: GitOid,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitCommitTags {
+    pub(crate) nearest: Option<GitTag>,
+    pub(crate) tags: Vec<GitTag>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitCommitDetail {
+    pub(crate) commit: GitCommit,
+    pub(crate) merge: Option<GitMerge>,
+    pub(crate) signature: Option<GitCommitSignature>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) enum GitCommitSignature {
+    Gpg,
+    Ssh,
+    Unknown,
+    X509,
+}
+
+impl From<&[u8]> for GitCommitSignature {
+    fn from(signature: &[u8]) -> Self {
+        let signature = String::from_utf8_lossy(signature);
+        match signature.trim_start().lines().next().unwrap_or_default() {
+            "-----BEGIN PGP SIGNATURE-----" => GitCommitSignature::Gpg,
+            "-----BEGIN SSH SIGNATURE-----" => GitCommitSignature::Ssh,
+            "-----BEGIN SIGNED MESSAGE-----" => GitCommitSignature::X509,
+            _ => GitCommitSignature::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) enum GitDeltaStatus {
+    Added,
+    Conflicted,
+    Copied,
+    Deleted,
+    Ignored,
+    Modified,
+    Renamed,
+    Typechange,
+    Unmodified,
+    Unreadable,
+    Untracked,
+}
+
+impl From<Delta> for GitDeltaStatus {
+    fn from(d: Delta) -> Self {
+        match d {
+            Delta::Added => GitDeltaStatus::Added,
+            Delta::Conflicted => GitDeltaStatus::Conflicted,
+            Delta::Copied => GitDeltaStatus::Copied,
+            Delta::Deleted => GitDeltaStatus::Deleted,
+            Delta::Ignored => GitDeltaStatus::Ignored,
+            Delta::Modified => GitDeltaStatus::Modified,
+            Delta::Renamed => GitDeltaStatus::Renamed,
+            Delta::Typechange => GitDeltaStatus::Typechange,
+            Delta::Unmodified => GitDeltaStatus::Unmodified,
+            Delta::Unreadable => GitDeltaStatus::Unreadable,
+            Delta::Untracked => GitDeltaStatus::Untracked,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitDiffFile {
+    pub(crate) hunks: Vec<GitDiffHunk>,
+    pub(crate) is_binary: bool,
+    pub(crate) new_path: Option<MaybeLossyUtf8>,
+    pub(crate) old_path: Option<MaybeLossyUtf8>,
+    pub(crate) status: GitDeltaStatus,
+}
+
+impl GitDiffFile {
+    pub(crate) fn line_stats(&self) -> (usize, usize) {
+        self.hunks
+            .iter()
+            .flat_map(|h| h.lines.iter())
+            .fold((0, 0), |(insertions, deletions), l| match l.origin {
+                GitDiffLineType::Addition => (insertions + 1, deletions),
+                GitDiffLineType::Context => (insertions, deletions),
+                GitDiffLineType::Deletion => (insertions, deletions + 1),
+            })
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        self.new_path
+            .as_ref()
+            .or(self.old_path.as_ref())
+            .map(|p| p.0.as_str())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitDiffHunk {
+    pub(crate) header: MaybeLossyUtf8,
+    pub(crate) lines: Vec<GitDiffLine>,
+    pub(crate) new_lines: u32,
+    pub(crate) new_start: u32,
+    pub(crate) old_lines: u32,
+    pub(crate) old_start: u32,
+}
+
+impl GitDiffHunk {
+    pub(crate) fn split_rows(&self) -> Vec<(Option<&GitDiffLine>, Option<&GitDiffLine>)> {
+        let mut rows = vec![];
+        let mut i = 0;
+        while i < self.lines.len() {
+            if self.lines[i].origin == GitDiffLineType::Context {
+                rows.push((Some(&self.lines[i]), Some(&self.lines[i])));
+                i += 1;
+                continue;
+            }
+            let deletions: Vec<_> = self.lines[i..]
+                .iter()
+                .take_while(|l| l.origin == GitDiffLineType::Deletion)
+                .collect();
+            let additions: Vec<_> = self.lines[i + deletions.len()..]
+                .iter()
+                .take_while(|l| l.origin == GitDiffLineType::Addition)
+                .collect();
+            for k in 0..deletions.len().max(additions.len()) {
+                rows.push((deletions.get(k).copied(), additions.get(k).copied()));
+            }
+            i += deletions.len() + additions.len();
+        }
+        rows
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitDiffLine {
+    pub(crate) content: MaybeLossyUtf8,
+    pub(crate) new_lineno: Option<u32>,
+    pub(crate) old_lineno: Option<u32>,
+    pub(crate) origin: GitDiffLineType,
+    pub(crate) spans: Option<Vec<GitDiffSpan>>,
+}
+
+impl GitDiffLine {
+    pub(crate) fn prefix(&self) -> char {
+        match self.origin {
+            GitDiffLineType::Addition => '+',
+            GitDiffLineType::Context => ' ',
+            GitDiffLineType::Deletion => '-',
+        }
+    }
+
+    pub(crate) fn segments(&self) -> Vec<(bool, &str)> {
+        let content = self.content.0.trim_end_matches(['\r', '\n']);
+        let Some(spans) = &self.spans else {
+            return vec![(false, content)];
+        };
+        let mut segments = vec![];
+        let mut offset = 0;
+        for span in spans {
+            let start = span.start.min(content.len());
+            let end = span.end.min(content.len());
+            if offset < start {
+                segments.push((false, &content[offset..start]));
+            }
+            if start < end {
+                segments.push((true, &content[start..end]));
+            }
+            offset = offset.max(end);
+        }
+        if offset < content.len() {
+            segments.push((false, &content[offset..]));
+        }
+        segments
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) enum GitDiffLineType {
+    Addition,
+    Context,
+    Deletion,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) struct GitDiffSpan {
+    pub(crate) end: usize,
+    pub(crate) start: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct GitDiffStats {
+    pub(crate) deletions: usize,
+    pub(crate) files_changed: usize,
+    pub(crate) insertions: usize,
 }
 
 #[derive(Debug, Serialize)]
@@ -108,7 +376,54 @@ impl std::fmt::Display for GitIndex {
     }
 }
 
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct GitLanguageStats {
+    pub(crate) file_count: usize,
+    pub(crate) name: String,
+    pub(crate) size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitLicense {
+    pub(crate) id: GitOid,
+    pub(crate) name: String,
+    pub(crate) path: MaybeLossyUtf8,
+    pub(crate) spdx_id: String,
+}
+
+/// One page of the entries of a tree, like `git ls-tree -l`.
+#[derive(Debug, Serialize)]
+pub(crate) struct GitManifest {
+    pub(crate) entries: Vec<GitManifestEntry>,
+    pub(crate) next_cursor: Option<usize>,
+    pub(crate) total: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitManifestEntry {
+    pub(crate) filemode: i32,
+    pub(crate) id: GitOid,
+    pub(crate) kind: Option<GitObjectType>,
+    pub(crate) path: MaybeLossyUtf8,
+    pub(crate) size: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitMerge {
+    pub(crate) branch: Option<String>,
+    pub(crate) id: GitOid,
+    pub(crate) short_id: String,
+    pub(crate) summary: MaybeLossyUtf8,
+}
+
+/// Either the merge commit or the paths that conflict.
 #[derive(Debug, Serialize)]
+pub(crate) struct GitMergeResult {
+    pub(crate) commit: Option<GitCommit>,
+    pub(crate) conflicts: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
 pub(crate) enum GitObjectType {
     Any,
     Blob,
@@ -187,6 +502,47 @@ impl Serialize for GitOid {
     }
 }
 
+/// Any object as `git cat-file -p` would print it.
+#[derive(Debug, Serialize)]
+pub(crate) struct GitObject {
+    pub(crate) content: MaybeLossyUtf8,
+    pub(crate) id: GitOid,
+    pub(crate) kind: GitObjectType,
+    pub(crate) size: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitPack {
+    pub(crate) index_size: Option<u64>,
+    pub(crate) name: String,
+    pub(crate) object_count: Option<u32>,
+    pub(crate) size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitPackVerification {
+    pub(crate) checksum: Option<String>,
+    pub(crate) error: Option<String>,
+    pub(crate) name: String,
+    pub(crate) object_count: Option<u32>,
+    pub(crate) valid: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct GitReadme {
+    pub(crate) content: MaybeLossyUtf8,
+    pub(crate) name: MaybeLossyUtf8,
+}
+
+/// A ref moved by a fetch; `old` is `None` for new refs.
+#[derive(Debug, Serialize)]
+pub(crate) struct GitRefUpdate {
+    pub(crate) name: String,
+    pub(crate) new: GitOid,
+    pub(crate) old: Option<GitOid>,
+    pub(crate) shorthand: String,
+}
+
 #[derive(Debug, Serialize)]
 pub(crate) struct GitReference {
     pub(crate) kind: Option<GitReferenceType>,
@@ -213,20 +569,97 @@ impl From<ReferenceType> for GitReferenceType {
 
 #[derive(Debug, Serialize)]
 pub(crate) struct GitRemote {
+    pub(crate) fetched_at: Option<OffsetDateTime>,
     pub(crate) name: MaybeLossyUtf8,
     pub(crate) url: MaybeLossyUtf8,
 }
 
+/// A ref advertised by a remote, against the local tracking ref it fetches into.
 #[derive(Debug, Serialize)]
+pub(crate) struct GitRemoteRef {
+    pub(crate) name: MaybeLossyUtf8,
+    pub(crate) status: GitRemoteRefStatus,
+    pub(crate) target: GitOid,
+    pub(crate) tracking: Option<GitOid>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub(crate) enum GitRemoteRefStatus {
+    Missing,
+    Stale,
+    UpToDate,
+}
+
+/// An operation left in progress in the working tree, like a conflicted
+/// merge waiting to be committed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub(crate) enum GitRepositoryState {
+    ApplyMailbox,
+    ApplyMailboxOrRebase,
+    Bisect,
+    CherryPick,
+    CherryPickSequence,
+    Clean,
+    Merge,
+    Rebase,
+    RebaseInteractive,
+    RebaseMerge,
+    Revert,
+    RevertSequence,
+}
+
+impl GitRepositoryState {
+    /// The git command in progress, `None` when clean.
+    pub(crate) fn operation(&self) -> Option<&'static str> {
+        match self {
+            GitRepositoryState::ApplyMailbox => Some("am"),
+            GitRepositoryState::Bisect => Some("bisect"),
+            GitRepositoryState::CherryPick | GitRepositoryState::CherryPickSequence => {
+                Some("cherry-pick")
+            }
+            GitRepositoryState::Clean => None,
+            GitRepositoryState::Merge => Some("merge"),
+            GitRepositoryState::ApplyMailboxOrRebase
+            | GitRepositoryState::Rebase
+            | GitRepositoryState::RebaseInteractive
+            | GitRepositoryState::RebaseMerge => Some("rebase"),
+            GitRepositoryState::Revert | GitRepositoryState::RevertSequence => Some("revert"),
+        }
+    }
+}
+
+impl From<RepositoryState> for GitRepositoryState {
+    fn from(s: RepositoryState) -> Self {
+        match s {
+            RepositoryState::ApplyMailbox => GitRepositoryState::ApplyMailbox,
+            RepositoryState::ApplyMailboxOrRebase => GitRepositoryState::ApplyMailboxOrRebase,
+            RepositoryState::Bisect => GitRepositoryState::Bisect,
+            RepositoryState::CherryPick => GitRepositoryState::CherryPick,
+            RepositoryState::CherryPickSequence => GitRepositoryState::CherryPickSequence,
+            RepositoryState::Clean => GitRepositoryState::Clean,
+            RepositoryState::Merge => GitRepositoryState::Merge,
+            RepositoryState::Rebase => GitRepositoryState::Rebase,
+            RepositoryState::RebaseInteractive => GitRepositoryState::RebaseInteractive,
+            RepositoryState::RebaseMerge => GitRepositoryState::RebaseMerge,
+            RepositoryState::Revert => GitRepositoryState::Revert,
+            RepositoryState::RevertSequence => GitRepositoryState::RevertSequence,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub(crate) struct GitSignature {
+    pub(crate) avatar_url: Option<String>,
     pub(crate) email: MaybeLossyUtf8,
     pub(crate) name: MaybeLossyUtf8,
 }
 
-impl From<Signature<'_>> for GitSignature {
-    fn from(s: Signature<'_>) -> Self {
+impl GitSignature {
+    pub(super) fn new(s: &Signature<'_>, avatar_url_template: Option<&str>) -> Self {
+        let email = MaybeLossyUtf8::from(s.email_bytes());
         GitSignature {
-            email: s.email_bytes().into(),
+            avatar_url: avatar_url_template.map(|t| avatar_url(t, &email.0)),
+            email,
             name: s.name_bytes().into(),
         }
     }
@@ -261,15 +694,31 @@ impl Serialize for GitStatusFlag {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct GitSummary {
+    pub(crate) entries: Vec<GitTree>,
+    pub(crate) head: GitCommit,
+    pub(crate) partial_clone: bool,
+    pub(crate) readme: Option<GitReadme>,
+    pub(crate) shallow: bool,
+    pub(crate) state: GitRepositoryState,
+    pub(crate) stats: GitTreeStats,
+    pub(crate) worktree: Option<GitWorktree>,
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub(crate) struct GitTag {
+    pub(crate) commit: Option<GitOid>,
+    pub(crate) message: Option<MaybeLossyUtf8>,
     pub(crate) name: MaybeLossyUtf8,
     pub(crate) shorthand: MaybeLossyUtf8,
+    pub(crate) tagger: Option<GitSignature>,
     pub(crate) target: GitOid,
     pub(crate) target_short: String,
+    pub(crate) time: Option<OffsetDateTime>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 pub(crate) struct GitTree {
     pub(crate) filemode: i32,
     pub(crate) id: GitOid,
@@ -277,17 +726,44 @@ pub(crate) struct GitTree {
     pub(crate) name: MaybeLossyUtf8,
     pub(crate) root: String,
     pub(crate) short_id: String,
+    pub(crate) size: Option<usize>,
+    /// URL from `.gitmodules` for submodule entries, whose `id` is the
+    /// pinned commit in that repository.
+    pub(crate) submodule_url: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct GitTreeStats {
+    pub(crate) file_count: usize,
+    pub(crate) languages: Vec<GitLanguageStats>,
+    pub(crate) license: Option<String>,
+    pub(crate) size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct GitUnreleased {
+    pub(crate) commits: Vec<GitCommit>,
+    pub(crate) tag: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub(crate) struct GitUpstream {
+    pub(crate) ahead: usize,
+    pub(crate) behind: usize,
     pub(crate) name: MaybeLossyUtf8,
     pub(crate) shorthand: MaybeLossyUtf8,
     pub(crate) target: GitOid,
     pub(crate) target_short: String,
 }
 
-#[derive(Debug, Default, Serialize)]
+#[derive(Clone, Debug, Serialize)]
+pub(crate) struct GitWorktree {
+    pub(crate) branch: Option<String>,
+    pub(crate) name: Option<String>,
+    pub(crate) path: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
 pub(crate) struct MaybeLossyUtf8(pub(crate) String);
 
 impl std::fmt::Display for MaybeLossyUtf8 {
@@ -304,10 +780,6 @@ impl From<String> for MaybeLossyUtf8 {
 
 impl From<&[u8]> for MaybeLossyUtf8 {
     fn from(bytes: &[u8]) -> Self {
-        MaybeLossyUtf8(
-            String::from_utf8(bytes.into())
-                .map_err(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
-                .expect("from_utf8_lossy() is infallible"),
-        )
+        MaybeLossyUtf8(String::from_utf8_lossy(bytes).into_owned())
     }
 }
@@ -0,0 +1,59 @@
+use std::fmt::Write;
+
+use super::{model::GitRefUpdate, GitRepository, GitResult, NotifyConfig, ShortIdGetter};
+use crate::service::mail::Mail;
+
+const MAX_NOTIFIED_COMMITS: usize = 50;
+
+impl GitRepository {
+    /// One line per commit in `old..new`, newest first.
+    fn describe_update(&self, update: &GitRefUpdate) -> GitResult<String> {
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.push(update.new.0)?;
+        if let Some(old) = &update.old {
+            revwalk.hide(old.0)?;
+        }
+        let mut body = String::new();
+        for (i, id) in revwalk.enumerate() {
+            let commit = self.repo.find_commit(id?)?;
+            if i == MAX_NOTIFIED_COMMITS {
+                body.push_str("...\n");
+                break;
+            }
+            let _ = writeln!(
+                body,
+                "{} {}",
+                commit.as_object().get_short_id(),
+                String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())
+            );
+        }
+        Ok(body)
+    }
+
+    /// The mails about the new commits of watched branches, for the
+    /// configured recipients. Failures are logged rather than failing the
+    /// update.
+    pub(crate) fn ref_update_mails(
+        &self,
+        config: &NotifyConfig,
+        updates: &[GitRefUpdate],
+    ) -> Vec<Mail> {
+        if config.recipients.is_empty() {
+            return vec![];
+        }
+        updates
+            .iter()
+            .filter(|u| config.branches.contains(&u.shorthand))
+            .filter_map(|update| match self.describe_update(update) {
+                Ok(body) => Some(Mail {
+                    body,
+                    subject: format!("{} updated", update.shorthand),
+                }),
+                Err(e) => {
+                    tracing::warn!("failed to describe update of {}: {e}", update.name);
+                    None
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use git2::Indexer;
+
+use super::{
+    model::{GitPack, GitPackVerification},
+    GitError, GitRepository, GitResult,
+};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+static SCRATCH_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Directory the indexer writes its rebuilt pack and index into, removed on drop.
+struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    fn new() -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(
+            "wit-verify-pack-{}-{}",
+            std::process::id(),
+            SCRATCH_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir(&path)?;
+        Ok(ScratchDir(path))
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Object count from the 12 byte pack header.
+fn read_object_count(path: &Path) -> Option<u32> {
+    let mut header = [0; 12];
+    File::open(path).ok()?.read_exact(&mut header).ok()?;
+    if &header[..4] != b"PACK" {
+        return None;
+    }
+    Some(u32::from_be_bytes([
+        header[8], header[9], header[10], header[11],
+    ]))
+}
+
+impl GitRepository {
+    fn pack_dir(&self) -> PathBuf {
+        self.repo.commondir().join("objects").join("pack")
+    }
+
+    pub(crate) fn list_pack(&self) -> GitResult<Vec<GitPack>> {
+        let dir = self.pack_dir();
+        if !dir.is_dir() {
+            return Ok(vec![]);
+        }
+        let mut packs = vec![];
+        for entry in std::fs::read_dir(&dir)?.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|e| e != "pack") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            packs.push(GitPack {
+                index_size: std::fs::metadata(path.with_extension("idx"))
+                    .ok()
+                    .map(|m| m.len()),
+                name: name.to_string(),
+                object_count: read_object_count(&path),
+                size: entry.metadata()?.len(),
+            });
+        }
+        packs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(packs)
+    }
+
+    /// Like `git verify-pack`, re-indexes the pack from scratch, which
+    /// inflates and hashes every object and checks the trailing checksum.
+    pub(crate) fn verify_pack(&self, name: &str) -> GitResult<GitPackVerification> {
+        let not_found = || GitError::ObjectNotFound(format!("pack {name} not found"));
+        let checksum = name
+            .strip_prefix("pack-")
+            .filter(|s| !s.is_empty() && s.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(not_found)?;
+        let path = self.pack_dir().join(format!("{name}.pack"));
+        if !path.is_file() {
+            return Err(not_found());
+        }
+        let result = (|| -> GitResult<String> {
+            let scratch = ScratchDir::new()?;
+            let odb = self.repo.odb()?;
+            let mut indexer = Indexer::new(Some(&odb), &scratch.0, 0, true)?;
+            let mut file = File::open(&path)?;
+            let mut buf = vec![0; CHUNK_SIZE];
+            loop {
+                self.deadline.check()?;
+                let n = file.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                indexer.write_all(&buf[..n])?;
+            }
+            Ok(indexer.commit()?)
+        })();
+        let (actual, error) = match result {
+            Ok(actual) => (Some(actual), None),
+            Err(e @ (GitError::Cancelled | GitError::Timeout)) => return Err(e),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        let valid = actual.as_deref() == Some(checksum);
+        Ok(GitPackVerification {
+            error: error.or_else(|| {
+                (!valid).then(|| String::from("pack checksum does not match its name"))
+            }),
+            checksum: actual,
+            name: name.to_string(),
+            object_count: read_object_count(&path),
+            valid,
+        })
+    }
+}
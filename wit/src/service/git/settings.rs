@@ -0,0 +1,94 @@
+use std::{sync::Arc, time::Duration};
+
+use super::{
+    budget::DEFAULT_MEMORY_BUDGET, CancelOnDrop, Deadline, GitError, GitRepository, GitResult,
+};
+use crate::service::task;
+
+const DEFAULT_GIT_TIMEOUT: Duration = Duration::from_secs(25);
+
+/// Per-repository settings, kept by the router and attached to every
+/// repository it opens.
+#[derive(Debug)]
+pub(crate) struct GitSettings {
+    /// With `{hash}` replaced by the SHA-256 of the lowercased email.
+    pub(crate) avatar_url_template: Option<String>,
+    /// From `wit.toml`; see [`GitRepository::default_branch`].
+    pub(crate) default_branch: Option<String>,
+    /// How long a single request may keep a blocking git task busy.
+    pub(crate) git_timeout: Duration,
+    /// The most bytes a single request may load into memory at once.
+    pub(crate) memory_budget: usize,
+}
+
+impl GitSettings {
+    /// Bounds how long libgit2 waits to connect to a remote and for each
+    /// read from it by `git_timeout`, which the deadline of a git task cannot
+    /// interrupt.
+    ///
+    /// # Safety
+    ///
+    /// Sets libgit2 globals without synchronization, so no other thread may
+    /// use libgit2 at the same time.
+    pub(crate) unsafe fn apply_network_timeout(&self) -> GitResult<()> {
+        let millis = i32::try_from(self.git_timeout.as_millis()).unwrap_or(i32::MAX);
+        git2::opts::set_server_connect_timeout_in_milliseconds(millis)?;
+        git2::opts::set_server_timeout_in_milliseconds(millis)?;
+        Ok(())
+    }
+}
+
+impl Default for GitSettings {
+    fn default() -> Self {
+        GitSettings {
+            avatar_url_template: None,
+            default_branch: None,
+            git_timeout: DEFAULT_GIT_TIMEOUT,
+            memory_budget: DEFAULT_MEMORY_BUDGET,
+        }
+    }
+}
+
+/// A repository root together with its settings, cheap to clone into each
+/// request.
+#[derive(Clone, Debug)]
+pub(crate) struct RepoHandle {
+    pub(crate) root: String,
+    settings: Arc<GitSettings>,
+}
+
+impl RepoHandle {
+    pub(crate) fn new(root: String, settings: GitSettings) -> Self {
+        RepoHandle {
+            root,
+            settings: Arc::new(settings),
+        }
+    }
+
+    pub(crate) fn open(&self) -> GitResult<GitRepository> {
+        Ok(GitRepository::open(&self.root)?.with_settings(self.settings.clone()))
+    }
+
+    /// See [`Deadline::after`].
+    pub(crate) fn deadline(&self) -> (Deadline, CancelOnDrop) {
+        Deadline::after(self.settings.git_timeout)
+    }
+
+    /// Runs `f` on the blocking pool against a freshly opened repository.
+    pub(crate) async fn spawn<F, T>(self, f: F) -> GitResult<T>
+    where
+        F: FnOnce(&GitRepository) -> GitResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (deadline, _cancel) = self.deadline();
+        task::spawn_blocking(move || {
+            deadline.check()?;
+            f(&self.open()?.with_deadline(deadline))
+        })
+        .await
+        .unwrap_or_else(|e| match e.try_into_panic() {
+            Ok(payload) => std::panic::resume_unwind(payload),
+            Err(e) => Err(GitError::Unhandled(format!("git task cancelled: {e}"))),
+        })
+    }
+}
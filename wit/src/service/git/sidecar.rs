@@ -0,0 +1,134 @@
+use std::path::PathBuf;
+
+use git2::{BranchType, ConfigLevel, ErrorCode, Reference};
+use serde::Deserialize;
+
+use super::{model::GitDefaultBranch, GitRepository, GitResult, IdGetter};
+
+const DEFAULT_BRANCH_KEY: &str = "wit.defaultBranch";
+const SIDECAR_FILE: &str = "wit.toml";
+
+/// Per-repository settings read from `wit.toml` at the root of the working
+/// tree, or inside the git directory of a bare repository.
+#[derive(Debug, Default, Deserialize)]
+pub(crate) struct RepoConfig {
+    pub(crate) default_branch: Option<String>,
+    pub(crate) description: Option<String>,
+    #[serde(default)]
+    pub(crate) notify: NotifyConfig,
+}
+
+/// Who is emailed when a fetch moves one of `branches`, named by shorthand
+/// such as `main` or `origin/main`.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub(crate) struct NotifyConfig {
+    #[serde(default)]
+    pub(crate) branches: Vec<String>,
+    #[serde(default)]
+    pub(crate) recipients: Vec<String>,
+}
+
+impl RepoConfig {
+    fn parse(s: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(s)
+    }
+}
+
+impl GitRepository {
+    /// Branch treated as the default instead of the one HEAD points to, for
+    /// mirrors whose HEAD is wrong. One set through the API is kept in the
+    /// repository config and wins over `default_branch` in `wit.toml`; HEAD
+    /// itself is left untouched.
+    fn default_branch_override(&self) -> GitResult<Option<String>> {
+        match self.repo.config()?.get_string(DEFAULT_BRANCH_KEY) {
+            Ok(name) => Ok(Some(name)),
+            Err(e) if e.code() == ErrorCode::NotFound => Ok(self.settings.default_branch.clone()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub(crate) fn default_branch(&self) -> GitResult<GitDefaultBranch> {
+        let reference = self.default_reference()?;
+        Ok(GitDefaultBranch {
+            name: String::from_utf8_lossy(reference.shorthand_bytes()).into_owned(),
+            overridden: self.default_branch_override()?.is_some(),
+            target: reference.get_id(),
+        })
+    }
+
+    /// The override when one is set, otherwise HEAD.
+    pub(super) fn default_reference(&self) -> GitResult<Reference<'_>> {
+        match self.default_branch_override()? {
+            Some(name) => Ok(self
+                .repo
+                .find_branch(&name, BranchType::Local)?
+                .into_reference()),
+            None => Ok(self.repo.head()?),
+        }
+    }
+
+    /// Checks that the branch exists before overriding; `None` goes back to
+    /// `wit.toml`, or to following HEAD.
+    pub(crate) fn override_default_branch(
+        &self,
+        name: Option<String>,
+    ) -> GitResult<GitDefaultBranch> {
+        let mut config = self.repo.config()?.open_level(ConfigLevel::Local)?;
+        match &name {
+            Some(name) => {
+                self.repo.find_branch(name, BranchType::Local)?;
+                config.set_str(DEFAULT_BRANCH_KEY, name)?;
+            }
+            None => match config.remove(DEFAULT_BRANCH_KEY) {
+                Err(e) if e.code() != ErrorCode::NotFound => return Err(e.into()),
+                _ => {}
+            },
+        }
+        self.default_branch()
+    }
+
+    fn sidecar_path(&self) -> PathBuf {
+        self.repo
+            .workdir()
+            .unwrap_or_else(|| self.repo.path())
+            .join(SIDECAR_FILE)
+    }
+
+    /// Missing or invalid files fall back to the defaults.
+    pub(crate) fn load_config(&self) -> RepoConfig {
+        let path = self.sidecar_path();
+        let Ok(s) = std::fs::read_to_string(&path) else {
+            return RepoConfig::default();
+        };
+        RepoConfig::parse(&s).unwrap_or_else(|e| {
+            tracing::warn!("invalid repository config {path:?}: {e}");
+            RepoConfig::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repo_config() {
+        let config = RepoConfig::parse(
+            "description = \"A git viewer\"\ndefault_branch = \"main\"\nvisibility = \"public\"\n",
+        )
+        .unwrap_or_else(|e| panic!("parse repo config failed: {e:?}"));
+        assert_eq!(config.default_branch.as_deref(), Some("main"));
+        assert_eq!(config.description.as_deref(), Some("A git viewer"));
+
+        assert!(config.notify.recipients.is_empty());
+
+        let config = RepoConfig::parse(
+            "[notify]\nbranches = [\"origin/main\"]\nrecipients = [\"dev@example.com\"]\n",
+        )
+        .unwrap_or_else(|e| panic!("parse repo config failed: {e:?}"));
+        assert_eq!(config.notify.branches, ["origin/main"]);
+        assert_eq!(config.notify.recipients, ["dev@example.com"]);
+
+        assert!(RepoConfig::parse("description = 1").is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use super::model::{GitBranch, GitReference, GitStatus, GitTag};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SortKey {
+    Date,
+    Name,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub(crate) struct SortOptions {
+    #[serde(default)]
+    pub(crate) direction: SortDirection,
+    #[serde(rename = "sort")]
+    pub(crate) key: Option<SortKey>,
+}
+
+pub(crate) trait Sortable {
+    fn sort_date(&self) -> Option<OffsetDateTime>;
+    fn sort_name(&self) -> &str;
+}
+
+impl Sortable for GitBranch {
+    fn sort_date(&self) -> Option<OffsetDateTime> {
+        self.tip.as_ref().map(|c| c.time)
+    }
+
+    fn sort_name(&self) -> &str {
+        &self.shorthand.0
+    }
+}
+
+impl Sortable for GitReference {
+    fn sort_date(&self) -> Option<OffsetDateTime> {
+        None
+    }
+
+    fn sort_name(&self) -> &str {
+        &self.name.0
+    }
+}
+
+impl Sortable for GitStatus {
+    fn sort_date(&self) -> Option<OffsetDateTime> {
+        None
+    }
+
+    fn sort_name(&self) -> &str {
+        &self.path.0
+    }
+}
+
+impl Sortable for GitTag {
+    fn sort_date(&self) -> Option<OffsetDateTime> {
+        self.time
+    }
+
+    fn sort_name(&self) -> &str {
+        &self.shorthand.0
+    }
+}
+
+impl SortOptions {
+    /// Entries without a date are ordered by name when sorting by date.
+    pub(crate) fn apply<T: Sortable>(&self, items: &mut [T]) {
+        match self.key {
+            Some(SortKey::Date) => {
+                items.sort_by(|a, b| {
+                    (a.sort_date(), a.sort_name()).cmp(&(b.sort_date(), b.sort_name()))
+                });
+            }
+            Some(SortKey::Name) => items.sort_by(|a, b| a.sort_name().cmp(b.sort_name())),
+            None => {}
+        }
+        if self.direction == SortDirection::Desc {
+            items.reverse();
+        }
+    }
+
+    pub(crate) fn is_sorted_by(&self, key: &SortKey) -> bool {
+        self.key.as_ref() == Some(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::*;
+
+    fn tag(name: &str, time: Option<OffsetDateTime>) -> GitTag {
+        GitTag {
+            commit: None,
+            message: None,
+            name: format!("refs/tags/{name}").into(),
+            shorthand: String::from(name).into(),
+            tagger: None,
+            target: git2::Oid::zero().into(),
+            target_short: String::new(),
+            time,
+        }
+    }
+
+    #[test]
+    fn test_apply() {
+        let now = OffsetDateTime::now_utc();
+        let tags = || {
+            vec![
+                tag("v2", Some(now)),
+                tag("v1", Some(now - Duration::days(1))),
+                tag("v0", None),
+            ]
+        };
+        let sample = [
+            (None, SortDirection::Asc, ["v2", "v1", "v0"]),
+            (None, SortDirection::Desc, ["v0", "v1", "v2"]),
+            (Some(SortKey::Name), SortDirection::Asc, ["v0", "v1", "v2"]),
+            (Some(SortKey::Date), SortDirection::Asc, ["v0", "v1", "v2"]),
+            (Some(SortKey::Date), SortDirection::Desc, ["v2", "v1", "v0"]),
+        ];
+        for (key, direction, expected) in sample.into_iter() {
+            let mut items = tags();
+            SortOptions { direction, key }.apply(&mut items);
+            let names: Vec<_> = items.iter().map(|t| t.shorthand.0.as_str()).collect();
+            assert_eq!(names, expected, "{key:?} {direction:?}");
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+
+use git2::{ObjectType, Tree};
+
+use super::{
+    model::{GitObjectType, GitTree},
+    GitRepository, GitResult,
+};
+
+const GITMODULES: &str = ".gitmodules";
+
+impl GitRepository {
+    /// Fills in the URLs of the submodules among `entries`, listed from
+    /// `root`. `.gitmodules` is only read when there are any.
+    pub(super) fn add_submodule_urls(
+        &self,
+        root: &Tree<'_>,
+        entries: &mut [GitTree],
+    ) -> GitResult<()> {
+        let mut submodules = entries
+            .iter_mut()
+            .filter(|entry| entry.kind == Some(GitObjectType::Commit))
+            .peekable();
+        if submodules.peek().is_none() {
+            return Ok(());
+        }
+        let urls = self.submodule_urls(root)?;
+        for entry in submodules {
+            entry.submodule_url = urls.get(&format!("{}{}", entry.root, entry.name)).cloned();
+        }
+        Ok(())
+    }
+
+    /// Submodule URLs by path, from the `.gitmodules` of `root` rather than
+    /// of the working tree, so that they match the commit being viewed.
+    fn submodule_urls(&self, root: &Tree<'_>) -> GitResult<HashMap<String, String>> {
+        let Some(entry) = root.get_name(GITMODULES) else {
+            return Ok(HashMap::new());
+        };
+        if entry.kind() != Some(ObjectType::Blob) {
+            return Ok(HashMap::new());
+        }
+        let (size, _) = self.repo.odb()?.read_header(entry.id())?;
+        self.check_budget(GITMODULES, size, "the submodules are not shown")?;
+        let blob = self.repo.find_blob(entry.id())?;
+        Ok(parse_gitmodules(&String::from_utf8_lossy(blob.content())))
+    }
+}
+
+/// Reads `submodule.<name>.path` and `submodule.<name>.url` from the git
+/// config syntax of `.gitmodules`, and pairs them up by name.
+fn parse_gitmodules(content: &str) -> HashMap<String, String> {
+    let mut paths = HashMap::new();
+    let mut urls = HashMap::new();
+    let mut section = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(header) = line.strip_prefix('[') {
+            section = header
+                .split_once(']')
+                .and_then(|(header, _)| parse_section(header));
+            continue;
+        }
+        let (Some(name), Some((key, value))) = (&section, line.split_once('=')) else {
+            continue;
+        };
+        let map = match key.trim().to_ascii_lowercase().as_str() {
+            "path" => &mut paths,
+            "url" => &mut urls,
+            _ => continue,
+        };
+        map.insert(name.clone(), parse_value(value));
+    }
+    paths
+        .into_iter()
+        .filter_map(|(name, path)| Some((path, urls.remove(&name)?)))
+        .collect()
+}
+
+/// The name of a `submodule "<name>"` section header.
+fn parse_section(header: &str) -> Option<String> {
+    let (kind, name) = header.trim().split_once(char::is_whitespace)?;
+    if !kind.eq_ignore_ascii_case("submodule") {
+        return None;
+    }
+    let name = name.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some(name.replace("\\\"", "\"").replace("\\\\", "\\"))
+}
+
+/// A value with its comment dropped, its quotes and escapes resolved, and
+/// whitespace outside quotes trimmed.
+fn parse_value(value: &str) -> String {
+    let mut parsed = String::new();
+    let mut end = 0;
+    let mut quoted = false;
+    let mut chars = value.trim_start().chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' | ';' if !quoted => break,
+            '\\' => match chars.next() {
+                Some('n') => parsed.push('\n'),
+                Some('t') => parsed.push('\t'),
+                Some(c) => parsed.push(c),
+                None => {}
+            },
+            c => parsed.push(c),
+        }
+        if quoted || c == '"' || !c.is_whitespace() {
+            end = parsed.len();
+        }
+    }
+    parsed.truncate(end);
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::{FileMode, Oid, Repository};
+    use tempfile::tempdir;
+
+    use super::{
+        super::tests::{commit_with_signature, initialize_git_repo, set_git_head_to_branch},
+        *,
+    };
+
+    fn write_submodule_tree(repo: &Repository, url: &str) -> Oid {
+        let gitmodules = format!("[submodule \"lib\"]\n\tpath = lib\n\turl = {url}\n");
+        let blob = repo
+            .blob(gitmodules.as_bytes())
+            .unwrap_or_else(|e| panic!("write .gitmodules blob failed: {e:?}"));
+        let pinned = Oid::from_str("1234567890123456789012345678901234567890")
+            .unwrap_or_else(|e| panic!("parse oid failed: {e:?}"));
+        let mut builder = repo
+            .treebuilder(None)
+            .unwrap_or_else(|e| panic!("create tree builder failed: {e:?}"));
+        builder
+            .insert(GITMODULES, blob, FileMode::Blob.into())
+            .unwrap_or_else(|e| panic!("insert .gitmodules failed: {e:?}"));
+        builder
+            .insert("lib", pinned, FileMode::Commit.into())
+            .unwrap_or_else(|e| panic!("insert submodule failed: {e:?}"));
+        builder
+            .write()
+            .unwrap_or_else(|e| panic!("write tree failed: {e:?}"))
+    }
+
+    #[test]
+    fn test_list_tree_submodule_url() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        let tree_id = write_submodule_tree(&repo, "https://example.com/old.git");
+        let old = commit_with_signature(&repo, tree_id, "Add lib", "wit", "wit@example.com", None);
+        let tree_id = write_submodule_tree(&repo, "https://example.com/new.git");
+        let new = commit_with_signature(&repo, tree_id, "Move lib", "wit", "wit@example.com", None);
+
+        let repo: GitRepository = repo.into();
+        for (id, url) in [
+            (old, "https://example.com/old.git"),
+            (new, "https://example.com/new.git"),
+        ] {
+            let tree = repo
+                .list_tree_at(&id.into(), "")
+                .unwrap_or_else(|e| panic!("list tree of {id} should not fail: {e:?}"));
+            let lib = tree
+                .iter()
+                .find(|t| t.name.0 == "lib")
+                .unwrap_or_else(|| panic!("lib should be listed at {id}"));
+            assert_eq!(lib.submodule_url.as_deref(), Some(url), "{id}");
+        }
+    }
+
+    #[test]
+    fn test_parse_gitmodules() {
+        let urls = parse_gitmodules(concat!(
+            "# submodules\n",
+            "[submodule \"lib\"]\n\tpath = vendor/lib\n\tURL = https://example.com/lib.git ; main\n",
+            "[submodule \"v1.2\"]\n\tpath = \"docs # v1\"\n\turl = ../docs.git\n",
+            "[submodule \"orphan\"]\n\tpath = orphan\n",
+            "[core]\n\tpath = core\n\turl = ../core.git\n",
+        ));
+        assert_eq!(urls.len(), 2);
+        assert_eq!(
+            urls.get("vendor/lib").map(String::as_str),
+            Some("https://example.com/lib.git")
+        );
+        assert_eq!(
+            urls.get("docs # v1").map(String::as_str),
+            Some("../docs.git")
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use git2::Oid;
+use lru::LruCache;
+
+use super::{model::GitSummary, GitRepository, GitResult};
+
+const SUMMARY_CACHE_SIZE: usize = 16;
+
+type SummaryEntries = LruCache<(Oid, usize), GitSummary>;
+
+#[derive(Clone, Debug)]
+pub(crate) struct SummaryCache {
+    entries: Arc<Mutex<SummaryEntries>>,
+}
+
+impl Default for SummaryCache {
+    fn default() -> Self {
+        let capacity = NonZeroUsize::new(SUMMARY_CACHE_SIZE).unwrap_or(NonZeroUsize::MIN);
+        SummaryCache {
+            entries: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+}
+
+impl SummaryCache {
+    fn lock(&self) -> MutexGuard<'_, SummaryEntries> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn clear(&self) {
+        self.lock().clear();
+    }
+
+    pub(crate) fn summarize(&self, repo: &GitRepository, max_size: usize) -> GitResult<GitSummary> {
+        let key = (repo.head_id()?.0, max_size);
+        // An operation can start or finish without moving HEAD.
+        if let Some(mut summary) = self.lock().get(&key).cloned() {
+            summary.state = repo.state();
+            return Ok(summary);
+        }
+        let summary = repo.summarize(max_size)?;
+        self.lock().put(key, summary.clone());
+        Ok(summary)
+    }
+
+    /// Returns the number of cached entries and the capacity.
+    pub(crate) fn usage(&self) -> (usize, usize) {
+        let entries = self.lock();
+        (entries.len(), entries.cap().get())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use tempfile::tempdir;
+
+    use super::{
+        super::tests::{
+            commit_with_signature, create_file_with_content, initialize_git_repo,
+            set_git_head_to_branch, write_index_tree,
+        },
+        *,
+    };
+
+    #[test]
+    fn test_summarize() {
+        let dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = dir.path();
+        let repo = initialize_git_repo(path);
+
+        set_git_head_to_branch(&repo, "main");
+        create_file_with_content(path.join("README.md"), "# wit\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        let commit_id = commit_with_signature(
+            &repo,
+            tree_id,
+            "Initial commit",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+
+        let repo: GitRepository = repo.into();
+        let cache = SummaryCache::default();
+        for _ in 0..2 {
+            let summary = cache.summarize(&repo, 1024).unwrap_or_else(|e| {
+                panic!("summarize in git repo {path:?} should not fail: {e:?}")
+            });
+            assert_eq!(summary.head.id.0, commit_id);
+            assert_eq!(summary.entries.len(), 1);
+            assert_eq!(summary.stats.file_count, 1);
+            let readme = summary
+                .readme
+                .unwrap_or_else(|| panic!("README.md should be picked up as readme"));
+            assert_eq!(readme.name.0, "README.md");
+            assert_eq!(readme.content.0, "# wit\n");
+        }
+        assert_eq!(cache.lock().len(), 1);
+    }
+}
@@ -0,0 +1,256 @@
+use std::fmt::Write;
+
+use git2::{Branch, BranchType, Commit, Diff, Index, Oid, Signature, Time};
+use serde::Deserialize;
+
+use super::{
+    mailbox,
+    model::{GitCommit, GitMergeResult},
+    GitError, GitRepository, GitResult, MAX_COMMIT_COUNT,
+};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MergeStrategy {
+    FfOnly,
+    #[default]
+    MergeCommit,
+    Squash,
+}
+
+fn conflict_paths(index: &Index) -> GitResult<Vec<String>> {
+    Ok(index
+        .conflicts()?
+        .filter_map(|c| {
+            let c = c.ok()?;
+            let entry = c.our.or(c.their).or(c.ancestor)?;
+            Some(String::from_utf8_lossy(&entry.path).into_owned())
+        })
+        .collect())
+}
+
+impl GitRepository {
+    /// From the repository config, or `wit` when none is set.
+    fn committer(&self) -> GitResult<Signature<'static>> {
+        Ok(self
+            .repo
+            .signature()
+            .or_else(|_| Signature::now("wit", "wit@localhost"))?)
+    }
+
+    /// The branch checked out in a working tree is refused, since its files
+    /// would no longer match.
+    fn target_branch(&self, name: &str) -> GitResult<(Branch<'_>, Commit<'_>)> {
+        let branch = self.repo.find_branch(name, BranchType::Local)?;
+        if !self.repo.is_bare() && branch.is_head() {
+            return Err(GitError::Conflict(format!(
+                "{name} is checked out in the working tree"
+            )));
+        }
+        let tip = branch.get().peel_to_commit()?;
+        Ok((branch, tip))
+    }
+
+    /// Moves `branch` from `old` to `new` unless it moved in the meantime.
+    fn advance_branch(&self, branch: &Branch<'_>, old: Oid, new: Oid, log: &str) -> GitResult<()> {
+        let name = branch.get().name().unwrap_or_default();
+        self.repo
+            .reference_matching(name, new, true, old, log)
+            .map_err(|e| GitError::Conflict(format!("{name} was updated concurrently: {e}")))?;
+        Ok(())
+    }
+
+    /// Commits the tree of `index` on `branch` unless merging left conflicts,
+    /// which are listed in the error instead.
+    fn commit_index(
+        &self,
+        branch: &Branch<'_>,
+        mut index: Index,
+        message: &str,
+        parents: &[&Commit<'_>],
+        log: &str,
+    ) -> GitResult<Commit<'_>> {
+        if index.has_conflicts() {
+            return Err(GitError::Conflict(format!(
+                "conflicts in {}",
+                conflict_paths(&index)?.join(", ")
+            )));
+        }
+        let tree = self.repo.find_tree(index.write_tree_to(&self.repo)?)?;
+        let committer = self.committer()?;
+        let id = self
+            .repo
+            .commit(None, &committer, &committer, message, &tree, parents)?;
+        let commit = self.repo.find_commit(id)?;
+        self.advance_branch(branch, parents[0].id(), id, log)?;
+        Ok(commit)
+    }
+
+    /// Like `git revert`, undoes `id` in a new commit on `branch`. `mainline`
+    /// picks the parent to keep when reverting a merge.
+    pub(crate) fn revert_commit(
+        &self,
+        id: Oid,
+        branch: &str,
+        mainline: Option<u32>,
+    ) -> GitResult<GitCommit> {
+        let commit = self.repo.find_commit(id)?;
+        let parent_count = commit.parent_count();
+        let mainline = match mainline {
+            None if parent_count > 1 => {
+                return Err(GitError::InvalidArgument(format!(
+                    "{id} is a merge; pick the parent to keep with mainline"
+                )))
+            }
+            Some(n) if parent_count < 2 || n == 0 || n as usize > parent_count => {
+                return Err(GitError::InvalidArgument(format!(
+                    "mainline {n} is not a parent number of {id}"
+                )))
+            }
+            mainline => mainline.unwrap_or_default(),
+        };
+        let (target, tip) = self.target_branch(branch)?;
+        let index = self.repo.revert_commit(&commit, &tip, mainline, None)?;
+        let subject = format!(
+            "Revert \"{}\"",
+            String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())
+        );
+        let message = format!("{subject}\n\nThis reverts commit {id}.\n");
+        let revert = self.commit_index(
+            &target,
+            index,
+            &message,
+            &[&tip],
+            &format!("revert: {subject}"),
+        )?;
+        Ok(self.convert_to_commit(&revert, None))
+    }
+
+    /// `* summary` lines of the commits in `tip..head`, oldest first.
+    fn squash_message(&self, base: &str, head: &str, tip: Oid, id: Oid) -> GitResult<String> {
+        let mut revwalk = self.repo.revwalk()?;
+        revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::REVERSE)?;
+        revwalk.push(id)?;
+        revwalk.hide(tip)?;
+        let mut message = format!("Squashed {head} into {base}\n\n");
+        for id in revwalk.take(MAX_COMMIT_COUNT) {
+            let commit = self.repo.find_commit(id?)?;
+            let _ = writeln!(
+                message,
+                "* {}",
+                String::from_utf8_lossy(commit.summary_bytes().unwrap_or_default())
+            );
+        }
+        Ok(message)
+    }
+
+    /// Merges `head` into the branch `base` without touching a working tree.
+    /// Conflicts are returned instead of a commit and leave `base` as is.
+    /// When `head` is already merged, the tip of `base` is returned.
+    pub(crate) fn merge(
+        &self,
+        base: &str,
+        head: &str,
+        strategy: MergeStrategy,
+    ) -> GitResult<GitMergeResult> {
+        let (target, tip) = self.target_branch(base)?;
+        let theirs = self.repo.revparse_single(head)?.peel_to_commit()?;
+        let merged = |commit: &Commit<'_>| GitMergeResult {
+            commit: Some(self.convert_to_commit(commit, None)),
+            conflicts: vec![],
+        };
+        if tip.id() == theirs.id() || self.repo.graph_descendant_of(tip.id(), theirs.id())? {
+            return Ok(merged(&tip));
+        }
+        if let MergeStrategy::FfOnly = strategy {
+            if !self.repo.graph_descendant_of(theirs.id(), tip.id())? {
+                return Err(GitError::Conflict(format!(
+                    "{base} cannot be fast-forwarded to {head}"
+                )));
+            }
+            self.advance_branch(
+                &target,
+                tip.id(),
+                theirs.id(),
+                &format!("merge {head}: Fast-forward"),
+            )?;
+            return Ok(merged(&theirs));
+        }
+        let index = self.repo.merge_commits(&tip, &theirs, None)?;
+        if index.has_conflicts() {
+            return Ok(GitMergeResult {
+                commit: None,
+                conflicts: conflict_paths(&index)?,
+            });
+        }
+        let commit = match strategy {
+            MergeStrategy::Squash => {
+                let message = self.squash_message(base, head, tip.id(), theirs.id())?;
+                self.commit_index(
+                    &target,
+                    index,
+                    &message,
+                    &[&tip],
+                    &format!("merge {head}: Squashed"),
+                )?
+            }
+            _ => self.commit_index(
+                &target,
+                index,
+                &format!("Merge {head} into {base}\n"),
+                &[&tip, &theirs],
+                &format!("merge {head}: Merge made by wit"),
+            )?,
+        };
+        Ok(merged(&commit))
+    }
+
+    /// Like `git am`, commits each patch of `mbox` on top of `branch`. The
+    /// branch only moves when every patch applies.
+    pub(crate) fn apply_patch(&self, branch: &str, mbox: &str) -> GitResult<Vec<GitCommit>> {
+        let patches = mailbox::parse_mbox(mbox).ok_or_else(|| {
+            GitError::InvalidPatch(String::from(
+                "expected git format-patch output with a From header and a diff",
+            ))
+        })?;
+        let (target, tip) = self.target_branch(branch)?;
+        let committer = self.committer()?;
+        let count = patches.len();
+        let mut parent = tip.clone();
+        let mut commits = vec![];
+        for (i, patch) in patches.into_iter().enumerate() {
+            self.deadline.check()?;
+            let subject = patch.message.lines().next().unwrap_or_default().to_string();
+            let diff = Diff::from_buffer(patch.diff.as_bytes())
+                .map_err(|e| GitError::InvalidPatch(format!("{subject:?}: {}", e.message())))?;
+            let mut index = self
+                .repo
+                .apply_to_tree(&parent.tree()?, &diff, None)
+                .map_err(|e| {
+                    GitError::Conflict(format!(
+                        "patch {}/{count} {subject:?} does not apply: {}",
+                        i + 1,
+                        e.message()
+                    ))
+                })?;
+            let tree = self.repo.find_tree(index.write_tree_to(&self.repo)?)?;
+            let time = patch.date.map_or_else(
+                || committer.when(),
+                |d| Time::new(d.unix_timestamp(), d.offset().whole_minutes().into()),
+            );
+            let author = Signature::new(&patch.author_name, &patch.author_email, &time)?;
+            let id =
+                self.repo
+                    .commit(None, &author, &committer, &patch.message, &tree, &[&parent])?;
+            parent = self.repo.find_commit(id)?;
+            commits.push(self.convert_to_commit(&parent, None));
+        }
+        self.advance_branch(
+            &target,
+            tip.id(),
+            parent.id(),
+            &format!("am: applied {count} patches"),
+        )?;
+        Ok(commits)
+    }
+}
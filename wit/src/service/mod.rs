@@ -1 +1,4 @@
 pub(crate) mod git;
+pub(crate) mod mail;
+pub(crate) mod search;
+pub(crate) mod task;
@@ -0,0 +1,54 @@
+use tantivy::{directory::error::OpenDirectoryError, TantivyError};
+
+use crate::service::git::GitError;
+
+pub(crate) type SearchResult<T> = Result<T, SearchError>;
+
+#[derive(Debug)]
+pub(crate) enum SearchError {
+    Git(GitError),
+    InvalidPattern(String),
+    Unhandled(String),
+}
+
+impl std::fmt::Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SearchError::Git(e) => write!(f, "GitError: {e}"),
+            SearchError::InvalidPattern(message) => write!(f, "InvalidPattern: {message}"),
+            SearchError::Unhandled(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl From<GitError> for SearchError {
+    fn from(e: GitError) -> Self {
+        SearchError::Git(e)
+    }
+}
+
+impl From<OpenDirectoryError> for SearchError {
+    fn from(e: OpenDirectoryError) -> Self {
+        SearchError::Unhandled(format!("Unhandled OpenDirectoryError: {e}"))
+    }
+}
+
+impl From<regex::Error> for SearchError {
+    fn from(e: regex::Error) -> Self {
+        SearchError::InvalidPattern(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for SearchError {
+    fn from(e: std::io::Error) -> Self {
+        SearchError::Unhandled(format!("Unhandled IoError: {e}"))
+    }
+}
+
+impl From<TantivyError> for SearchError {
+    fn from(e: TantivyError) -> Self {
+        SearchError::Unhandled(format!("Unhandled TantivyError: {e}"))
+    }
+}
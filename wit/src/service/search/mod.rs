@@ -0,0 +1,542 @@
+mod error;
+pub(crate) mod model;
+mod path;
+
+use std::{path::Path, sync::Arc, time::Duration};
+
+use regex::RegexBuilder;
+use tantivy::{
+    collector::TopDocs,
+    directory::MmapDirectory,
+    doc,
+    query::QueryParser,
+    schema::{Field, Schema, Value, STORED, STRING, TEXT},
+    Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument,
+};
+use tokio::task::JoinHandle;
+
+pub(crate) use self::error::{SearchError, SearchResult};
+use self::model::{CodeMatch, PathMatch};
+pub(crate) use self::path::PathFinder;
+use crate::service::{
+    git::{model::GitOid, GitError, GitRepository, GitResult, RepoHandle},
+    task,
+};
+
+const INDEX_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_INDEXED_BLOB_SIZE: usize = 1024 * 1024;
+const MAX_GREP_PATTERN_SIZE: usize = 1024 * 1024;
+const MAX_MATCHES_PER_FILE: usize = 5;
+const MAX_SEARCH_RESULTS: usize = 100;
+const WRITER_MEMORY_BUDGET: usize = 50_000_000;
+
+#[derive(Clone)]
+pub(crate) struct SearchIndex {
+    inner: Arc<SearchIndexInner>,
+}
+
+struct SearchIndexInner {
+    fields: SearchFields,
+    index: Index,
+    reader: IndexReader,
+    repo: RepoHandle,
+}
+
+#[derive(Clone, Copy)]
+struct SearchFields {
+    content: Field,
+    path: Field,
+}
+
+impl std::fmt::Debug for SearchIndex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SearchIndex {{ repo_root: {:?} }}", self.inner.repo.root)
+    }
+}
+
+impl SearchIndex {
+    pub(crate) fn indexed_head(&self) -> SearchResult<Option<String>> {
+        Ok(self.inner.index.load_metas()?.payload)
+    }
+
+    pub(crate) fn open<P>(data_dir: P, repo: RepoHandle) -> SearchResult<SearchIndex>
+    where
+        P: AsRef<Path>,
+    {
+        let path = data_dir.as_ref().join("search");
+        std::fs::create_dir_all(&path)?;
+        let mut builder = Schema::builder();
+        let fields = SearchFields {
+            content: builder.add_text_field("content", TEXT | STORED),
+            path: builder.add_text_field("path", STRING | STORED),
+        };
+        let index = Index::open_or_create(MmapDirectory::open(&path)?, builder.build())?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        Ok(SearchIndex {
+            inner: Arc::new(SearchIndexInner {
+                fields,
+                index,
+                reader,
+                repo,
+            }),
+        })
+    }
+
+    pub(crate) fn refresh(&self) -> SearchResult<bool> {
+        let repo = self.inner.repo.open()?;
+        let head_id = repo.head_id()?;
+        let head = head_id.to_string();
+        if self.indexed_head()?.is_some_and(|h| h.eq(&head)) {
+            return Ok(false);
+        }
+        let SearchFields { content, path } = self.inner.fields;
+        let mut writer: IndexWriter = self.inner.index.writer(WRITER_MEMORY_BUDGET)?;
+        writer.delete_all_documents()?;
+        let mut result = Ok(());
+        repo.walk_text_blobs(&head_id, MAX_INDEXED_BLOB_SIZE, |p, c| {
+            result = writer
+                .add_document(doc!(path => p, content => c))
+                .map(|_| ());
+            result.is_ok()
+        })?;
+        result?;
+        let mut commit = writer.prepare_commit()?;
+        commit.set_payload(&head);
+        commit.commit()?;
+        self.inner.reader.reload()?;
+        Ok(true)
+    }
+
+    fn search_code(&self, query: &str) -> SearchResult<Vec<CodeMatch>> {
+        let SearchFields { content, path } = self.inner.fields;
+        let terms = split_terms(query);
+        let (query, _) =
+            QueryParser::for_index(&self.inner.index, vec![content]).parse_query_lenient(query);
+        let searcher = self.inner.reader.searcher();
+        let mut matches = vec![];
+        for (_, address) in searcher.search(&query, &TopDocs::with_limit(MAX_SEARCH_RESULTS))? {
+            let doc = searcher.doc::<TantivyDocument>(address)?;
+            let (Some(p), Some(c)) = (
+                doc.get_first(path).and_then(|v| v.as_str()),
+                doc.get_first(content).and_then(|v| v.as_str()),
+            ) else {
+                continue;
+            };
+            match_lines(p, c, |line| find_terms(line, &terms), &mut matches);
+            if matches.len() >= MAX_SEARCH_RESULTS {
+                break;
+            }
+        }
+        matches.truncate(MAX_SEARCH_RESULTS);
+        Ok(matches)
+    }
+
+    pub(crate) fn spawn_indexer(&self) -> JoinHandle<()> {
+        let search = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(INDEX_INTERVAL);
+            loop {
+                interval.tick().await;
+                let search = search.clone();
+                match tokio::task::spawn_blocking(move || search.refresh()).await {
+                    Ok(Ok(true)) => tracing::info!("search index refreshed"),
+                    Ok(Ok(false)) => {}
+                    Ok(Err(e)) => tracing::warn!("failed to refresh search index: {e}"),
+                    Err(e) => tracing::error!("search indexer task failed: {e}"),
+                }
+            }
+        })
+    }
+}
+
+fn find_terms(line: &str, terms: &[String]) -> Vec<(usize, usize)> {
+    let lowercase = line.to_ascii_lowercase();
+    terms
+        .iter()
+        .flat_map(|t| {
+            lowercase
+                .match_indices(t.as_str())
+                .map(|(start, t)| (start, start + t.len()))
+        })
+        .collect()
+}
+
+pub(crate) async fn grep(
+    repo: RepoHandle,
+    reference: String,
+    pattern: String,
+) -> SearchResult<Vec<CodeMatch>> {
+    let regex = RegexBuilder::new(&pattern)
+        .size_limit(MAX_GREP_PATTERN_SIZE)
+        .build()?;
+    spawn(move || {
+        let repo = repo.open()?;
+        let id = repo.resolve_commit(&reference)?;
+        Ok(grep_tree(&repo, &id, |line| {
+            regex
+                .find_iter(line)
+                .map(|m| (m.start(), m.end()))
+                .collect()
+        })?)
+    })
+    .await
+}
+
+fn grep_code(repo: &GitRepository, query: &str) -> GitResult<Vec<CodeMatch>> {
+    let terms = split_terms(query);
+    if terms.is_empty() {
+        return Ok(vec![]);
+    }
+    grep_tree(repo, &repo.head_id()?, |line| find_terms(line, &terms))
+}
+
+fn grep_tree<F>(repo: &GitRepository, id: &GitOid, find: F) -> GitResult<Vec<CodeMatch>>
+where
+    F: Fn(&str) -> Vec<(usize, usize)>,
+{
+    let mut matches = vec![];
+    repo.walk_text_blobs(id, MAX_INDEXED_BLOB_SIZE, |p, c| {
+        match_lines(p, c, &find, &mut matches);
+        matches.len() < MAX_SEARCH_RESULTS
+    })?;
+    matches.truncate(MAX_SEARCH_RESULTS);
+    Ok(matches)
+}
+
+fn match_lines<F>(path: &str, content: &str, find: F, matches: &mut Vec<CodeMatch>)
+where
+    F: Fn(&str) -> Vec<(usize, usize)>,
+{
+    let found = content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let mut highlights = find(line);
+            if highlights.is_empty() {
+                return None;
+            }
+            highlights.sort_unstable();
+            highlights.dedup_by(|next, prev| {
+                if next.0 <= prev.1 {
+                    prev.1 = prev.1.max(next.1);
+                    true
+                } else {
+                    false
+                }
+            });
+            Some(CodeMatch {
+                highlights,
+                line: line.to_string(),
+                line_number: i + 1,
+                path: path.to_string(),
+            })
+        })
+        .take(MAX_MATCHES_PER_FILE);
+    matches.extend(found);
+}
+
+pub(crate) async fn search_code(
+    search: Option<SearchIndex>,
+    repo: RepoHandle,
+    query: String,
+) -> SearchResult<Vec<CodeMatch>> {
+    let (deadline, _cancel) = repo.deadline();
+    spawn(move || match search {
+        Some(search) if search.indexed_head()?.is_some() => search.search_code(&query),
+        _ => Ok(grep_code(&repo.open()?.with_deadline(deadline), &query)?),
+    })
+    .await
+}
+
+pub(crate) async fn search_path(
+    finder: PathFinder,
+    repo: RepoHandle,
+    query: String,
+) -> SearchResult<Vec<PathMatch>> {
+    spawn(move || finder.search_path(&repo.open()?, &query)).await
+}
+
+async fn spawn<F, T>(f: F) -> SearchResult<T>
+where
+    F: FnOnce() -> SearchResult<T> + Send + 'static,
+    T: Send + 'static,
+{
+    task::spawn_blocking(f)
+        .await
+        .unwrap_or_else(|e| match e.try_into_panic() {
+            Ok(payload) => std::panic::resume_unwind(payload),
+            Err(e) => Err(GitError::Unhandled(format!("search task cancelled: {e}")).into()),
+        })
+}
+
+fn split_terms(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::service::git::{
+        tests::{
+            commit_with_signature, create_file_with_content, initialize_git_repo, write_index_tree,
+        },
+        GitSettings,
+    };
+
+    #[tokio::test]
+    async fn test_grep() {
+        let repo_dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = repo_dir.path();
+        let repo = initialize_git_repo(path);
+        create_file_with_content(path.join("README.md"), "git + web = wit\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        commit_with_signature(
+            &repo,
+            tree_id,
+            "Update files",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+        repo.tag_lightweight(
+            "v0.0.0",
+            &repo
+                .revparse_single("HEAD")
+                .unwrap_or_else(|e| panic!("resolve git head failed: {e:?}")),
+            false,
+        )
+        .unwrap_or_else(|e| panic!("create git tag failed: {e:?}"));
+        create_file_with_content(path.join("README.md"), "git + www = wit\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md")]);
+        commit_with_signature(
+            &repo,
+            tree_id,
+            "Update files",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+
+        let repo_root =
+            RepoHandle::new(path.to_string_lossy().into_owned(), GitSettings::default());
+        let run = |reference: &str, pattern: &str| {
+            grep(repo_root.clone(), reference.into(), pattern.into())
+        };
+
+        let matches = run("v0.0.0", r"w\w+b")
+            .await
+            .unwrap_or_else(|e| panic!("grep should not fail: {e:?}"));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, "README.md");
+        assert_eq!(matches[0].highlights, [(6, 9)]);
+
+        let matches = run("HEAD", r"w\w+b")
+            .await
+            .unwrap_or_else(|e| panic!("grep should not fail: {e:?}"));
+        assert!(matches.is_empty());
+
+        assert!(matches!(
+            run("HEAD", "(").await,
+            Err(SearchError::InvalidPattern(_))
+        ));
+        assert!(matches!(
+            run("missing", "wit").await,
+            Err(SearchError::Git(GitError::ReferenceNotFound(_)))
+        ));
+    }
+
+    #[test]
+    fn test_match_lines() {
+        let content = "fn main() {\n    println!(\"Hello, Wit!\");\n}\n// wit wit\n";
+        let mut matches = vec![];
+        let terms = split_terms("WIT");
+        match_lines(
+            "main.rs",
+            content,
+            |line| find_terms(line, &terms),
+            &mut matches,
+        );
+
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].line_number, 2);
+        assert_eq!(matches[0].highlights, [(21, 24)]);
+        assert_eq!(
+            matches[0].segments(),
+            [
+                (false, "    println!(\"Hello, "),
+                (true, "Wit"),
+                (false, "!\");")
+            ]
+        );
+        assert_eq!(matches[1].line_number, 4);
+        assert_eq!(matches[1].highlights, [(3, 6), (7, 10)]);
+    }
+
+    #[tokio::test]
+    async fn test_search_code() {
+        let repo_dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let data_dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = repo_dir.path();
+        let repo = initialize_git_repo(path);
+        create_file_with_content(path.join("README.md"), "git + web = wit\n");
+        create_file_with_content(path.join("LICENSE"), "BSD\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md"), Path::new("LICENSE")]);
+        commit_with_signature(
+            &repo,
+            tree_id,
+            "Update files",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+
+        let repo_root =
+            RepoHandle::new(path.to_string_lossy().into_owned(), GitSettings::default());
+        let search = SearchIndex::open(data_dir.path(), repo_root.clone())
+            .unwrap_or_else(|e| panic!("open search index should not fail: {e:?}"));
+        let run = |search: Option<SearchIndex>| {
+            search_code(search, repo_root.clone(), String::from("web"))
+        };
+
+        let grep = run(Some(search.clone()))
+            .await
+            .unwrap_or_else(|e| panic!("search_code should not fail: {e:?}"));
+        search
+            .refresh()
+            .unwrap_or_else(|e| panic!("refresh search index should not fail: {e:?}"));
+        let indexed = run(Some(search))
+            .await
+            .unwrap_or_else(|e| panic!("search_code should not fail: {e:?}"));
+
+        for matches in [grep, indexed] {
+            assert_eq!(matches.len(), 1);
+            assert_eq!(matches[0].path, "README.md");
+            assert_eq!(matches[0].line_number, 1);
+            assert_eq!(matches[0].highlights, [(6, 9)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_path() {
+        let repo_dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = repo_dir.path();
+        let repo = initialize_git_repo(path);
+        create_file_with_content(path.join("README.md"), "");
+        create_file_with_content(path.join("main.rs"), "");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md"), Path::new("main.rs")]);
+        commit_with_signature(
+            &repo,
+            tree_id,
+            "Update files",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+
+        let repo_root =
+            RepoHandle::new(path.to_string_lossy().into_owned(), GitSettings::default());
+        let finder = PathFinder::default();
+        let run = |query: &str| search_path(finder.clone(), repo_root.clone(), query.into());
+
+        let matches = run("rdme")
+            .await
+            .unwrap_or_else(|e| panic!("search_path should not fail: {e:?}"));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, "README.md");
+        assert_eq!(
+            matches[0].segments(),
+            [
+                (true, "R".into()),
+                (false, "EA".into()),
+                (true, "DME".into()),
+                (false, ".md".into()),
+            ]
+        );
+
+        create_file_with_content(path.join("lib.rs"), "");
+        let tree_id = write_index_tree(&repo, &[Path::new("lib.rs")]);
+        commit_with_signature(
+            &repo,
+            tree_id,
+            "Update files",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+        let matches = run("rs")
+            .await
+            .unwrap_or_else(|e| panic!("search_path should not fail: {e:?}"));
+        let paths = matches.iter().map(|m| m.path.as_str()).collect::<Vec<_>>();
+        assert_eq!(paths, ["lib.rs", "main.rs"]);
+    }
+
+    #[test]
+    fn test_refresh() {
+        let repo_dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let data_dir = tempdir().unwrap_or_else(|e| panic!("create tempdir failed: {e:?}"));
+        let path = repo_dir.path();
+        let repo = initialize_git_repo(path);
+        create_file_with_content(path.join("README.md"), "git + web = wit\n");
+        create_file_with_content(path.join("LICENSE"), "BSD\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("README.md"), Path::new("LICENSE")]);
+        commit_with_signature(
+            &repo,
+            tree_id,
+            "Update files",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+
+        let repo_root =
+            RepoHandle::new(path.to_string_lossy().into_owned(), GitSettings::default());
+        let search = SearchIndex::open(data_dir.path(), repo_root)
+            .unwrap_or_else(|e| panic!("open search index should not fail: {e:?}"));
+        assert!(search
+            .indexed_head()
+            .unwrap_or_else(|e| panic!("read indexed head should not fail: {e:?}"))
+            .is_none());
+
+        let refresh = || {
+            search
+                .refresh()
+                .unwrap_or_else(|e| panic!("refresh search index should not fail: {e:?}"))
+        };
+        let num_docs = || search.inner.reader.searcher().num_docs();
+        assert!(refresh());
+        assert_eq!(num_docs(), 2);
+        assert!(!refresh());
+
+        create_file_with_content(path.join("main.rs"), "fn main() {}\n");
+        let tree_id = write_index_tree(&repo, &[Path::new("main.rs")]);
+        commit_with_signature(
+            &repo,
+            tree_id,
+            "Update files",
+            "wit",
+            "wit@example.com",
+            None,
+        );
+        assert!(refresh());
+        assert_eq!(num_docs(), 3);
+        let head = repo
+            .head()
+            .and_then(|h| h.peel_to_commit())
+            .unwrap_or_else(|e| panic!("resolve git head failed: {e:?}"));
+        assert_eq!(
+            search
+                .indexed_head()
+                .unwrap_or_else(|e| panic!("read indexed head should not fail: {e:?}")),
+            Some(head.id().to_string())
+        );
+    }
+}
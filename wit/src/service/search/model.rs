@@ -0,0 +1,48 @@
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CodeMatch {
+    pub(crate) highlights: Vec<(usize, usize)>,
+    pub(crate) line: String,
+    pub(crate) line_number: usize,
+    pub(crate) path: String,
+}
+
+impl CodeMatch {
+    pub(crate) fn segments(&self) -> Vec<(bool, &str)> {
+        let mut segments = vec![];
+        let mut offset = 0;
+        for &(start, end) in self.highlights.iter() {
+            if start > offset {
+                segments.push((false, &self.line[offset..start]));
+            }
+            segments.push((true, &self.line[start..end]));
+            offset = end;
+        }
+        if offset < self.line.len() {
+            segments.push((false, &self.line[offset..]));
+        }
+        segments
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct PathMatch {
+    pub(crate) indices: Vec<usize>,
+    pub(crate) path: String,
+    pub(crate) score: i64,
+}
+
+impl PathMatch {
+    pub(crate) fn segments(&self) -> Vec<(bool, String)> {
+        let mut segments: Vec<(bool, String)> = vec![];
+        for (i, c) in self.path.chars().enumerate() {
+            let highlight = self.indices.contains(&i);
+            match segments.last_mut() {
+                Some((h, text)) if *h == highlight => text.push(c),
+                _ => segments.push((highlight, c.into())),
+            }
+        }
+        segments
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+
+use super::{model::PathMatch, SearchResult};
+use crate::service::git::{model::GitOid, GitRepository};
+
+const MAX_PATH_RESULTS: usize = 50;
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PathFinder {
+    manifest: Arc<Mutex<Option<PathManifest>>>,
+}
+
+#[derive(Debug)]
+struct PathManifest {
+    head: String,
+    paths: Arc<Vec<String>>,
+}
+
+impl PathFinder {
+    fn lock(&self) -> MutexGuard<'_, Option<PathManifest>> {
+        self.manifest.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn clear(&self) {
+        *self.lock() = None;
+    }
+
+    fn manifest(&self, repo: &GitRepository, id: &GitOid) -> SearchResult<Arc<Vec<String>>> {
+        let head = id.to_string();
+        if let Some(manifest) = self.lock().as_ref().filter(|m| m.head.eq(&head)) {
+            return Ok(manifest.paths.clone());
+        }
+        let paths = Arc::new(repo.list_path(id)?);
+        *self.lock() = Some(PathManifest {
+            head,
+            paths: paths.clone(),
+        });
+        Ok(paths)
+    }
+
+    pub(crate) fn search_path(
+        &self,
+        repo: &GitRepository,
+        query: &str,
+    ) -> SearchResult<Vec<PathMatch>> {
+        let paths = self.manifest(repo, &repo.head_id()?)?;
+        let matcher = SkimMatcherV2::default();
+        let mut matches = paths
+            .iter()
+            .filter_map(|p| {
+                matcher
+                    .fuzzy_indices(p, query)
+                    .map(|(score, indices)| PathMatch {
+                        indices,
+                        path: p.clone(),
+                        score,
+                    })
+            })
+            .collect::<Vec<_>>();
+        matches.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.path.len().cmp(&b.path.len()))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        matches.truncate(MAX_PATH_RESULTS);
+        Ok(matches)
+    }
+}
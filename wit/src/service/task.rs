@@ -0,0 +1,46 @@
+use tokio::task::JoinHandle;
+
+tokio::task_local! {
+    /// From the `x-request-id` header of the request a task is handling.
+    pub(crate) static REQUEST_ID: String;
+}
+
+pub(crate) fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Like [`tokio::task::spawn_blocking`], with the request id of the calling
+/// task kept for `f`, so that a panic in it is logged with the id.
+pub(crate) fn spawn_blocking<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let request_id = current_request_id();
+    tokio::task::spawn_blocking(move || match request_id {
+        Some(request_id) => REQUEST_ID.sync_scope(request_id, f),
+        None => f(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_spawn_blocking_panic() {
+        let result = REQUEST_ID
+            .scope(String::from("42"), async {
+                spawn_blocking(|| panic!("{}", current_request_id().unwrap_or_default())).await
+            })
+            .await;
+        let payload = match result {
+            Ok(()) => panic!("blocking task should panic"),
+            Err(e) => e.into_panic(),
+        };
+        assert_eq!(
+            payload.downcast_ref::<String>().map(String::as_str),
+            Some("42")
+        );
+    }
+}